@@ -0,0 +1,45 @@
+use nalgebra::{Matrix4, Point3, Vector3};
+
+// Caja alineada a los ejes en coordenadas de mundo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Aabb { min, max }
+    }
+
+    pub fn from_center_size(center: Vector3<f32>, size: f32) -> Self {
+        let mitad = Vector3::new(size, size, size) / 2.0;
+        Aabb::new(center - mitad, center + mitad)
+    }
+
+    // Se solapan en volumen; tocarse por una cara no cuenta
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        (0..3).all(|axis| self.min[axis] < other.max[axis] && self.max[axis] > other.min[axis])
+    }
+
+    pub fn translated(&self, offset: Vector3<f32>) -> Aabb {
+        Aabb::new(self.min + offset, self.max + offset)
+    }
+
+    // Caja que contiene las 8 esquinas transformadas
+    pub fn transformed(&self, transform: &Matrix4<f32>) -> Aabb {
+        let mut min = Vector3::repeat(f32::INFINITY);
+        let mut max = Vector3::repeat(f32::NEG_INFINITY);
+        for corner in 0..8 {
+            let point = Point3::new(
+                if corner & 1 == 0 { self.min.x } else { self.max.x },
+                if corner & 2 == 0 { self.min.y } else { self.max.y },
+                if corner & 4 == 0 { self.min.z } else { self.max.z },
+            );
+            let transformed = transform.transform_point(&point).coords;
+            min = min.inf(&transformed);
+            max = max.sup(&transformed);
+        }
+        Aabb::new(min, max)
+    }
+}
@@ -0,0 +1,57 @@
+use nalgebra::Vector3;
+use crate::framebuffer::Framebuffer;
+use crate::tiles::Tile;
+
+// Promedia cuadros sucesivos con jitter mientras la cámara y la escena no cambian,
+// así la imagen converge a una sin escalones y con sombras suaves, ya que cada cuadro
+// ve la luz desde otro punto. La suma es en color
+// lineal; recién el promedio se lleva a 8 bits.
+pub struct Accumulator {
+    sum: Vec<Vector3<f32>>,
+    // Cuadro en curso; entra en `sum` solo si se completa
+    current: Vec<Vector3<f32>>,
+    pub frames: u32,
+}
+
+impl Accumulator {
+    pub fn new(width: usize, height: usize) -> Self {
+        Accumulator {
+            sum: vec![Vector3::zeros(); width * height],
+            current: vec![Vector3::zeros(); width * height],
+            frames: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.sum.fill(Vector3::zeros());
+        self.frames = 0;
+    }
+
+    // Guarda un tile del cuadro en curso y escribe en `target` el promedio de esa zona
+    // contando ya el cuadro nuevo. El cuadro entra en el promedio con `finish_frame`;
+    // si se cancela a medias basta con no llamarlo.
+    pub fn add_tile(&mut self, tile: &Tile, pixels: &[Vector3<f32>], target: &mut Framebuffer) {
+        let frames = (self.frames + 1) as f32;
+        for (row, colors) in pixels.chunks(tile.width).enumerate() {
+            let start = (tile.y + row) * target.width + tile.x;
+            let range = start..start + tile.width;
+            self.current[range.clone()].copy_from_slice(colors);
+            for ((sum, color), pixel) in self.sum[range.clone()].iter().zip(colors).zip(&mut target.buffer[range]) {
+                *pixel = pack(&((sum + color) / frames));
+            }
+        }
+    }
+
+    // Suma al promedio el cuadro en curso, que ya tiene todos sus tiles
+    pub fn finish_frame(&mut self) {
+        for (sum, color) in self.sum.iter_mut().zip(&self.current) {
+            *sum += color;
+        }
+        self.frames += 1;
+    }
+}
+
+fn pack(color: &Vector3<f32>) -> u32 {
+    let [r, g, b] = [color.x, color.y, color.z].map(|channel| (channel * 255.0).round().clamp(0.0, 255.0) as u32);
+    (r << 16) | (g << 8) | b
+}
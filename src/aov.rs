@@ -0,0 +1,154 @@
+use crate::exr::ExrImage;
+use crate::framebuffer::{srgb_to_linear, Framebuffer};
+
+// Datos del primer impacto de un pixel, para composición y depuración
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AovPixel {
+    // Distancia lineal a lo largo de la vista; infinito si el rayo no chocó
+    pub depth: f32,
+    pub normal: [f32; 3],
+    // Color de la textura sin luz, en sRGB
+    pub albedo: [f32; 3],
+    pub uv: [f32; 2],
+    // Índice del objeto + 1; 0 es el fondo
+    pub object_id: u32,
+    // `Material::id`; 0 es el fondo o un material sin nombre
+    pub material_id: u32,
+}
+
+impl AovPixel {
+    pub fn empty() -> Self {
+        AovPixel {
+            depth: f32::INFINITY,
+            normal: [0.0; 3],
+            albedo: [0.0; 3],
+            uv: [0.0; 2],
+            object_id: 0,
+            material_id: 0,
+        }
+    }
+}
+
+pub struct Aovs {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<AovPixel>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AovView {
+    Beauty,
+    Depth,
+    Normal,
+    Albedo,
+    Uv,
+    ObjectId,
+    MaterialId,
+}
+
+impl AovView {
+    pub fn next(self) -> Self {
+        match self {
+            AovView::Beauty => AovView::Depth,
+            AovView::Depth => AovView::Normal,
+            AovView::Normal => AovView::Albedo,
+            AovView::Albedo => AovView::Uv,
+            AovView::Uv => AovView::ObjectId,
+            AovView::ObjectId => AovView::MaterialId,
+            AovView::MaterialId => AovView::Beauty,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AovView::Beauty => "beauty",
+            AovView::Depth => "depth",
+            AovView::Normal => "normal",
+            AovView::Albedo => "albedo",
+            AovView::Uv => "uv",
+            AovView::ObjectId => "object_id",
+            AovView::MaterialId => "material_id",
+        }
+    }
+}
+
+impl Aovs {
+    pub fn new(width: usize, height: usize) -> Self {
+        Aovs {
+            width,
+            height,
+            pixels: vec![AovPixel::empty(); width * height],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.fill(AovPixel::empty());
+    }
+
+    // Imagen en falso color de una pasada, para verla en la ventana o guardarla en 8 bits
+    pub fn visualize(&self, view: AovView) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(self.width, self.height);
+
+        // La profundidad se normaliza entre el punto más cercano y el más lejano
+        let finite = self.pixels.iter().map(|pixel| pixel.depth).filter(|depth| depth.is_finite());
+        let near = finite.clone().fold(f32::INFINITY, f32::min);
+        let far = finite.fold(0.0, f32::max);
+
+        for (packed, pixel) in framebuffer.buffer.iter_mut().zip(&self.pixels) {
+            let color = match view {
+                AovView::Beauty => [0.0; 3],
+                AovView::Depth if pixel.depth.is_finite() => {
+                    let gray = 1.0 - (pixel.depth - near) / (far - near).max(1e-6);
+                    [gray; 3]
+                }
+                AovView::Depth => [0.0; 3],
+                AovView::Normal if pixel.object_id != 0 => pixel.normal.map(|n| n * 0.5 + 0.5),
+                AovView::Normal => [0.0; 3],
+                AovView::Albedo => pixel.albedo,
+                AovView::Uv => [pixel.uv[0], pixel.uv[1], 0.0],
+                AovView::ObjectId => id_color(pixel.object_id),
+                AovView::MaterialId => id_color(pixel.material_id),
+            };
+            let [r, g, b] = color.map(|channel| (channel * 255.0).round().clamp(0.0, 255.0) as u32);
+            *packed = (r << 16) | (g << 8) | b;
+        }
+
+        framebuffer
+    }
+
+    // Agrega todas las pasadas como capas del EXR
+    pub fn add_to_exr(&self, image: &mut ExrImage) {
+        image.add_float("depth.Z", self.pixels.iter().map(|pixel| pixel.depth).collect());
+        image.add_vector("normal", &self.pixels.iter().map(|pixel| pixel.normal).collect::<Vec<_>>());
+        image.add_rgb(Some("albedo"), &self.pixels.iter().map(|pixel| pixel.albedo.map(srgb_to_linear)).collect::<Vec<_>>());
+        image.add_float("uv.U", self.pixels.iter().map(|pixel| pixel.uv[0]).collect());
+        image.add_float("uv.V", self.pixels.iter().map(|pixel| pixel.uv[1]).collect());
+        image.add_uint("objectId.id", self.pixels.iter().map(|pixel| pixel.object_id).collect());
+        image.add_uint("materialId.id", self.pixels.iter().map(|pixel| pixel.material_id).collect());
+    }
+
+    // Valores crudos de una pasada como RGB flotante (para PFM)
+    pub fn channel(&self, view: AovView) -> Vec<[f32; 3]> {
+        self.pixels
+            .iter()
+            .map(|pixel| match view {
+                AovView::Beauty => [0.0; 3],
+                AovView::Depth => [pixel.depth; 3],
+                AovView::Normal => pixel.normal,
+                AovView::Albedo => pixel.albedo.map(srgb_to_linear),
+                AovView::Uv => [pixel.uv[0], pixel.uv[1], 0.0],
+                AovView::ObjectId => [pixel.object_id as f32; 3],
+                AovView::MaterialId => [pixel.material_id as f32; 3],
+            })
+            .collect()
+    }
+}
+
+// Color estable y bien separado para cada identificador
+fn id_color(id: u32) -> [f32; 3] {
+    if id == 0 {
+        return [0.0; 3];
+    }
+    let hash = id.wrapping_mul(2_654_435_761);
+    [(hash >> 24) & 0xFF, (hash >> 16) & 0xFF, (hash >> 8) & 0xFF].map(|channel| (channel as f32 / 255.0) * 0.8 + 0.2)
+}
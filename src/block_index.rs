@@ -0,0 +1,62 @@
+use nalgebra::Vector3;
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::picking::block_position;
+use crate::ray_intersect::RayIntersect;
+
+// Qué objeto de la escena ocupa cada bloque de la grilla. Se actualiza con cada edición
+// en vez de recorrer la lista de objetos.
+pub struct BlockIndex {
+    blocks: HashMap<Vector3<i32>, usize>,
+}
+
+impl BlockIndex {
+    pub fn new(objects: &[Arc<dyn RayIntersect>]) -> Self {
+        let mut index = BlockIndex { blocks: HashMap::new() };
+        for (position, object) in objects.iter().enumerate() {
+            if let Some(block) = block_of(object) {
+                index.blocks.insert(block, position);
+            }
+        }
+        index
+    }
+
+    // Posición en la escena del objeto que ocupa `block`
+    pub fn get(&self, block: &Vector3<i32>) -> Option<usize> {
+        self.blocks.get(block).copied()
+    }
+
+    // Agrega el objeto al final de la escena y devuelve su posición
+    pub fn place(&mut self, objects: &mut Vec<Arc<dyn RayIntersect>>, object: Arc<dyn RayIntersect>) -> usize {
+        let position = objects.len();
+        if let Some(block) = block_of(&object) {
+            self.blocks.insert(block, position);
+        }
+        objects.push(object);
+        position
+    }
+
+    // Quita el objeto de la posición `position`. El último de la lista pasa a ocupar su lugar,
+    // así no hay que correr el resto; el orden no importa porque todos se trazan y dan sombra.
+    pub fn remove(&mut self, objects: &mut Vec<Arc<dyn RayIntersect>>, position: usize) -> Arc<dyn RayIntersect> {
+        let last = objects.len() - 1;
+        let object = objects.swap_remove(position);
+        if let Some(block) = block_of(&object) {
+            if self.blocks.get(&block) == Some(&position) {
+                self.blocks.remove(&block);
+            }
+        }
+        if position < last {
+            if let Some(block) = block_of(&objects[position]) {
+                if self.blocks.get(&block) == Some(&last) {
+                    self.blocks.insert(block, position);
+                }
+            }
+        }
+        object
+    }
+}
+
+pub fn block_of(object: &Arc<dyn RayIntersect>) -> Option<Vector3<i32>> {
+    object.bounds().map(|bounds| block_position(&bounds))
+}
@@ -0,0 +1,311 @@
+use nalgebra_glm::Vec3;
+use crate::ray_intersect::{Intersect, RayIntersect, Material};
+use std::any::Any;
+use crate::aabb::Aabb;
+
+const PIXEL: f32 = 1.0 / 16.0;
+
+// Lado hacia el que mira un bloque orientado (norte = -z, este = +x)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Facing {
+    North,
+    South,
+    East,
+    West,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Half {
+    Bottom,
+    Top,
+}
+
+// Vecinos con los que se conectan cercas, muros y paneles
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Connections {
+    pub north: bool,
+    pub south: bool,
+    pub east: bool,
+    pub west: bool,
+}
+
+impl Connections {
+    pub fn new(north: bool, south: bool, east: bool, west: bool) -> Self {
+        Connections { north, south, east, west }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockShape {
+    Slab(Half),
+    Stairs(Facing, Half),
+    Fence(Connections),
+    Wall(Connections),
+    Pane(Connections),
+    Carpet,
+    // Nivel 0 es la fuente, 7 es el flujo más bajo
+    Fluid(u8),
+}
+
+// Caja alineada a los ejes en coordenadas del bloque ([0, 1] en cada eje).
+// `uv` guarda (u0, v0, u1, v1) por cara en el mismo orden que los materiales del Cube.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelBox {
+    pub min: Vec3,
+    pub max: Vec3,
+    pub uv: [[f32; 4]; 6],
+}
+
+impl ModelBox {
+    // Las UV por defecto recortan la textura según la posición de la caja dentro
+    // del bloque, igual que hace Minecraft, para que no se estire.
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        let uv = [
+            [1.0 - min.z, 1.0 - min.y, 1.0 - max.z, 1.0 - max.y],
+            [1.0 - min.z, 1.0 - min.y, 1.0 - max.z, 1.0 - max.y],
+            [min.x, min.z, max.x, max.z],
+            [min.x, min.z, max.x, max.z],
+            [1.0 - min.x, 1.0 - min.y, 1.0 - max.x, 1.0 - max.y],
+            [1.0 - min.x, 1.0 - min.y, 1.0 - max.x, 1.0 - max.y],
+        ];
+        ModelBox { min, max, uv }
+    }
+
+    // Versión en pixeles de textura (0..16), como en los modelos de Minecraft
+    pub fn from_pixels(min: [f32; 3], max: [f32; 3]) -> Self {
+        ModelBox::new(
+            Vec3::new(min[0], min[1], min[2]) * PIXEL,
+            Vec3::new(max[0], max[1], max[2]) * PIXEL,
+        )
+    }
+}
+
+impl BlockShape {
+    pub fn boxes(&self) -> Vec<ModelBox> {
+        match *self {
+            BlockShape::Slab(Half::Bottom) => vec![ModelBox::from_pixels([0.0, 0.0, 0.0], [16.0, 8.0, 16.0])],
+            BlockShape::Slab(Half::Top) => vec![ModelBox::from_pixels([0.0, 8.0, 0.0], [16.0, 16.0, 16.0])],
+            BlockShape::Stairs(facing, half) => {
+                let (base_y, step_y) = match half {
+                    Half::Bottom => ((0.0, 8.0), (8.0, 16.0)),
+                    Half::Top => ((8.0, 16.0), (0.0, 8.0)),
+                };
+                // El escalón alto queda del lado hacia el que mira la escalera
+                let (step_min, step_max) = match facing {
+                    Facing::North => ([0.0, step_y.0, 0.0], [16.0, step_y.1, 8.0]),
+                    Facing::South => ([0.0, step_y.0, 8.0], [16.0, step_y.1, 16.0]),
+                    Facing::East => ([8.0, step_y.0, 0.0], [16.0, step_y.1, 16.0]),
+                    Facing::West => ([0.0, step_y.0, 0.0], [8.0, step_y.1, 16.0]),
+                };
+                vec![
+                    ModelBox::from_pixels([0.0, base_y.0, 0.0], [16.0, base_y.1, 16.0]),
+                    ModelBox::from_pixels(step_min, step_max),
+                ]
+            }
+            BlockShape::Fence(connections) => {
+                let mut boxes = vec![ModelBox::from_pixels([6.0, 0.0, 6.0], [10.0, 16.0, 10.0])];
+                for (y0, y1) in [(6.0, 9.0), (12.0, 15.0)] {
+                    boxes.extend(arms(connections, 7.0, 9.0, y0, y1, 6.0, 10.0));
+                }
+                boxes
+            }
+            BlockShape::Wall(connections) => {
+                let mut boxes = vec![ModelBox::from_pixels([4.0, 0.0, 4.0], [12.0, 16.0, 12.0])];
+                boxes.extend(arms(connections, 5.0, 11.0, 0.0, 14.0, 4.0, 12.0));
+                boxes
+            }
+            BlockShape::Pane(connections) => {
+                let mut boxes = vec![ModelBox::from_pixels([7.0, 0.0, 7.0], [9.0, 16.0, 9.0])];
+                boxes.extend(arms(connections, 7.0, 9.0, 0.0, 16.0, 7.0, 9.0));
+                boxes
+            }
+            BlockShape::Carpet => vec![ModelBox::from_pixels([0.0, 0.0, 0.0], [16.0, 1.0, 16.0])],
+            BlockShape::Fluid(level) => {
+                let height = (8 - level.min(7)) as f32 / 9.0;
+                vec![ModelBox::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, height, 1.0))]
+            }
+        }
+    }
+}
+
+// Brazos que salen del poste central hacia cada vecino conectado.
+// `low`/`high` es el grosor del brazo y `post_min`/`post_max` el borde del poste.
+fn arms(connections: Connections, low: f32, high: f32, y0: f32, y1: f32, post_min: f32, post_max: f32) -> Vec<ModelBox> {
+    let mut boxes = Vec::new();
+    if connections.north {
+        boxes.push(ModelBox::from_pixels([low, y0, 0.0], [high, y1, post_min]));
+    }
+    if connections.south {
+        boxes.push(ModelBox::from_pixels([low, y0, post_max], [high, y1, 16.0]));
+    }
+    if connections.west {
+        boxes.push(ModelBox::from_pixels([0.0, y0, low], [post_min, y1, high]));
+    }
+    if connections.east {
+        boxes.push(ModelBox::from_pixels([post_max, y0, low], [16.0, y1, high]));
+    }
+    boxes
+}
+
+// Bloque formado por varias cajas: losas, escaleras, cercas, muros, paneles,
+// alfombras y fluidos. Ocupa el mismo espacio que un Cube con el mismo centro y tamaño.
+#[derive(Clone)]
+pub struct BlockModel {
+    pub center: Vec3,
+    pub size: f32,
+    pub shape: BlockShape,
+    pub boxes: Vec<ModelBox>,
+    pub materials: [Material; 6],
+}
+
+impl BlockModel {
+    pub fn new(center: Vec3, size: f32, shape: BlockShape, materials: [Material; 6]) -> Self {
+        BlockModel {
+            center,
+            size,
+            shape,
+            boxes: shape.boxes(),
+            materials,
+        }
+    }
+
+    fn origin(&self) -> Vec3 {
+        self.center - Vec3::new(self.size, self.size, self.size) / 2.0
+    }
+
+    // Límites de una caja en coordenadas de mundo
+    pub fn box_bounds(&self, model_box: &ModelBox) -> (Vec3, Vec3) {
+        let origin = self.origin();
+        (origin + model_box.min * self.size, origin + model_box.max * self.size)
+    }
+
+    fn face_uv(&self, model_box: &ModelBox, face_index: usize, point: &Vec3) -> (f32, f32) {
+        let (min, max) = self.box_bounds(model_box);
+        let fraction = |axis: usize| {
+            let extent = max[axis] - min[axis];
+            if extent.abs() < f32::EPSILON { 0.0 } else { ((point[axis] - min[axis]) / extent).clamp(0.0, 1.0) }
+        };
+
+        // (eje de u, eje de v) de cada cara, igual que en Cube
+        let (s, t) = match face_index {
+            0 | 1 => (fraction(2), fraction(1)),
+            2 | 3 => (fraction(0), fraction(2)),
+            _ => (fraction(0), fraction(1)),
+        };
+
+        let [u0, v0, u1, v1] = model_box.uv[face_index];
+        (u0 + (u1 - u0) * s, v0 + (v1 - v0) * t)
+    }
+}
+
+impl RayIntersect for BlockModel {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        let inv_dir = Vec3::new(1.0 / ray_direction.x, 1.0 / ray_direction.y, 1.0 / ray_direction.z);
+
+        let mut closest: Option<(f32, usize, &ModelBox)> = None;
+
+        for model_box in &self.boxes {
+            let (min, max) = self.box_bounds(model_box);
+            let t_min = (min - ray_origin).component_mul(&inv_dir);
+            let t_max = (max - ray_origin).component_mul(&inv_dir);
+
+            // Se guarda el eje por el que entra (y sale) el rayo para saber la cara
+            let mut t_enter = f32::NEG_INFINITY;
+            let mut t_exit = f32::INFINITY;
+            let mut enter_axis = 0;
+            let mut exit_axis = 0;
+            for axis in 0..3 {
+                let near = t_min[axis].min(t_max[axis]);
+                let far = t_min[axis].max(t_max[axis]);
+                if near > t_enter {
+                    t_enter = near;
+                    enter_axis = axis;
+                }
+                if far < t_exit {
+                    t_exit = far;
+                    exit_axis = axis;
+                }
+            }
+
+            if t_enter > t_exit || t_exit < 0.0 {
+                continue;
+            }
+
+            let (t_hit, axis, sign) = if t_enter < 0.0 {
+                (t_exit, exit_axis, ray_direction[exit_axis].signum())
+            } else {
+                (t_enter, enter_axis, -ray_direction[enter_axis].signum())
+            };
+            let face_index = axis * 2 + if sign > 0.0 { 1 } else { 0 };
+
+            if closest.is_none_or(|(t, _, _)| t_hit < t) {
+                closest = Some((t_hit, face_index, model_box));
+            }
+        }
+
+        let Some((t_hit, face_index, model_box)) = closest else {
+            return Intersect::empty();
+        };
+
+        let punto_encuentro = ray_origin + ray_direction * t_hit;
+        let mut normal = Vec3::new(0.0, 0.0, 0.0);
+        normal[face_index / 2] = if face_index % 2 == 1 { 1.0 } else { -1.0 };
+
+        let (u, v) = self.face_uv(model_box, face_index, &punto_encuentro);
+
+        Intersect::new(
+            punto_encuentro,
+            normal,
+            t_hit,
+            self.materials[face_index].clone(),
+            u.clamp(0.0, 1.0),
+            v.clamp(0.0, 1.0),
+        )
+    }
+
+    fn get_uv(&self, punto_encuentro: &Vec3) -> (f32, f32) {
+        for model_box in &self.boxes {
+            let (min, max) = self.box_bounds(model_box);
+            for axis in 0..3 {
+                let inside_others = (0..3)
+                    .filter(|&other| other != axis)
+                    .all(|other| punto_encuentro[other] >= min[other] - 0.001 && punto_encuentro[other] <= max[other] + 0.001);
+                if !inside_others {
+                    continue;
+                }
+                if (punto_encuentro[axis] - min[axis]).abs() < 0.001 {
+                    return self.face_uv(model_box, axis * 2, punto_encuentro);
+                }
+                if (punto_encuentro[axis] - max[axis]).abs() < 0.001 {
+                    return self.face_uv(model_box, axis * 2 + 1, punto_encuentro);
+                }
+            }
+        }
+        (0.0, 0.0)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn collision_boxes(&self) -> Vec<Aabb> {
+        if matches!(self.shape, BlockShape::Fluid(_)) || !self.materials.iter().any(|material| material.solid) {
+            return Vec::new();
+        }
+        self.boxes
+            .iter()
+            .map(|model_box| {
+                let (min, max) = self.box_bounds(model_box);
+                Aabb::new(min, max)
+            })
+            .collect()
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::from_center_size(self.center, self.size))
+    }
+}
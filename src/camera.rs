@@ -1,26 +1,339 @@
 use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
 
+// Ángulos de elevación de las vistas axonométricas clásicas
+const ISOMETRIC_PITCH: f32 = 0.615_479_7; // atan(1 / sqrt(2)) = 35.26°
+const DIMETRIC_PITCH: f32 = 0.463_647_6; // atan(1 / 2) = 26.57°, la proporción 2:1 del pixel art
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    // Campo de visión vertical en radianes
+    Perspective { fov_y: f32 },
+    // Altura visible en unidades de mundo
+    Orthographic { view_height: f32 },
+    // Panorama 360° x 180° (longitud en x, latitud en y)
+    Equirectangular,
+    // Ojo de pez circular inscrito en la altura de la imagen; `fov` es el ángulo total
+    Fisheye { fov: f32, mapping: FisheyeMapping },
+    // Cilindro vertical: ángulo horizontal `fov_x`, altura lineal con pixeles cuadrados
+    Cylindrical { fov_x: f32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FisheyeMapping {
+    // El radio en la imagen es proporcional al ángulo
+    Equidistant,
+    // Conserva las formas; r = tan(θ / 2)
+    Stereographic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApertureShape {
+    Circle,
+    Square,
+    // Diafragma poligonal con este número de hojas
+    Polygon(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StereoLayout {
+    // Ojo izquierdo a la izquierda, para visores VR
+    SideBySide,
+    // Ojo izquierdo arriba, lo habitual en panoramas 360° estéreo
+    OverUnder,
+    // Rojo para el ojo izquierdo, cian para el derecho
+    Anaglyph,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stereo {
+    pub interpupillary_distance: f32,
+    // Distancia a la que los dos ojos no tienen paralaje
+    pub convergence: f32,
+    pub layout: StereoLayout,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StereoEye {
+    // Desplazamiento lateral del ojo respecto a `eye` (positivo a la derecha)
+    pub offset: f32,
+    pub convergence: f32,
+}
+
+#[derive(Clone, PartialEq)]
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3,
     pub up: Vec3,
+    pub projection: Projection,
+    // Lente delgada: con apertura 0 la cámara es estenopeica
+    pub aperture: f32,
+    pub focus_distance: f32,
+    pub aperture_shape: ApertureShape,
+    // Inclinación del plano focal (tilt-shift) en radianes y giro de su eje alrededor de la vista
+    pub tilt: f32,
+    pub tilt_rotation: f32,
+    pub autofocus: bool,
+    pub stereo: Option<Stereo>,
+    // Solo en las cámaras de cada ojo que genera `eye_camera`
+    pub stereo_eye: Option<StereoEye>,
 }
 
 impl Camera {
     pub fn new(eye: Vec3, center: Vec3, up: Vec3) -> Self {
-        Camera { eye, center, up }
+        Camera {
+            eye,
+            center,
+            up,
+            // 90° de campo vertical, lo mismo que el plano z = -1 que se usaba antes
+            projection: Projection::Perspective { fov_y: PI / 2.0 },
+            aperture: 0.0,
+            focus_distance: (center - eye).magnitude(),
+            aperture_shape: ApertureShape::Circle,
+            tilt: 0.0,
+            tilt_rotation: 0.0,
+            autofocus: false,
+            stereo: None,
+            stereo_eye: None,
+        }
     }
 
-    pub fn base_change(&self, vector: &Vec3) -> Vec3 {
+    // Vista isométrica ortográfica mirando a `center` desde la esquina (+x, +y, -z)
+    pub fn isometric(center: Vec3, distance: f32, view_height: f32) -> Self {
+        Camera::axonometric(center, distance, view_height, ISOMETRIC_PITCH)
+    }
+
+    pub fn dimetric(center: Vec3, distance: f32, view_height: f32) -> Self {
+        Camera::axonometric(center, distance, view_height, DIMETRIC_PITCH)
+    }
+
+    fn axonometric(center: Vec3, distance: f32, view_height: f32, pitch: f32) -> Self {
+        let yaw = -PI / 4.0;
+        let eye = center + Vec3::new(
+            distance * yaw.cos() * pitch.cos(),
+            distance * pitch.sin(),
+            distance * yaw.sin() * pitch.cos(),
+        );
+        let mut camera = Camera::new(eye, center, Vec3::new(0.0, 1.0, 0.0));
+        camera.projection = Projection::Orthographic { view_height };
+        camera
+    }
+
+    // (adelante, derecha, arriba)
+    pub fn basis(&self) -> (Vec3, Vec3, Vec3) {
         let forward = (self.center - self.eye).normalize();
         let right = forward.cross(&self.up).normalize();
         let up = right.cross(&forward).normalize();
+        (forward, right, up)
+    }
+
+    pub fn base_change(&self, vector: &Vec3) -> Vec3 {
+        let (forward, right, up) = self.basis();
 
         let rotated = vector.x * right + vector.y * up - vector.z * forward;
         rotated.normalize()
     }
 
+    // Rayo primario para un punto de pantalla en [-1, 1] (y hacia arriba).
+    // Devuelve None fuera del área de imagen (por ejemplo fuera del círculo del ojo de pez).
+    pub fn primary_ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32) -> Option<(Vec3, Vec3)> {
+        let ray = self.center_eye_ray(screen_x, screen_y, aspect_ratio)?;
+        match self.stereo_eye {
+            Some(stereo_eye) => Some(self.offset_eye(ray, stereo_eye)),
+            None => Some(ray),
+        }
+    }
+
+    // Cámara de un ojo para estéreo: `side` es -1 para el izquierdo y 1 para el derecho
+    pub fn eye_camera(&self, side: f32) -> Camera {
+        let mut camera = self.clone();
+        camera.stereo_eye = self.stereo.map(|stereo| StereoEye {
+            offset: side * stereo.interpupillary_distance / 2.0,
+            convergence: stereo.convergence,
+        });
+        camera.stereo = None;
+        camera
+    }
+
+    // Mueve el origen del rayo al ojo y lo reorienta hacia el punto de convergencia.
+    // En los panoramas el ojo gira con la dirección de la vista (estéreo omnidireccional),
+    // así el paralaje es correcto en los 360°.
+    fn offset_eye(&self, (origin, direction): (Vec3, Vec3), stereo_eye: StereoEye) -> (Vec3, Vec3) {
+        let (forward, right, up) = self.basis();
+        let convergence = stereo_eye.convergence.max(0.1);
+
+        let (side, distance) = match self.projection {
+            Projection::Perspective { .. } | Projection::Orthographic { .. } => {
+                // Plano de convergencia paralelo a la pantalla: estéreo fuera de eje, sin toe-in
+                (right, convergence / direction.dot(&forward).max(1e-4))
+            }
+            _ => {
+                let side = direction.cross(&up);
+                let side = if side.magnitude() < 1e-4 { right } else { side.normalize() };
+                (side, convergence)
+            }
+        };
+
+        let target = origin + direction * distance;
+        let eye_origin = origin + side * stereo_eye.offset;
+        (eye_origin, (target - eye_origin).normalize())
+    }
+
+    fn center_eye_ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32) -> Option<(Vec3, Vec3)> {
+        let (forward, right, up) = self.basis();
+        let from_camera = |x: f32, y: f32, z: f32| (right * x + up * y + forward * z).normalize();
+
+        match self.projection {
+            Projection::Perspective { fov_y } => {
+                let half_height = (fov_y / 2.0).tan();
+                let direction = self.base_change(&Vec3::new(
+                    screen_x * aspect_ratio * half_height,
+                    screen_y * half_height,
+                    -1.0,
+                ));
+                Some((self.eye, direction))
+            }
+            Projection::Orthographic { view_height } => {
+                let half_height = view_height / 2.0;
+                let origin = self.eye
+                    + right * (screen_x * aspect_ratio * half_height)
+                    + up * (screen_y * half_height);
+                Some((origin, forward))
+            }
+            Projection::Equirectangular => {
+                let longitude = screen_x * PI;
+                let latitude = screen_y * PI / 2.0;
+                let direction = from_camera(
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                    latitude.cos() * longitude.cos(),
+                );
+                Some((self.eye, direction))
+            }
+            Projection::Fisheye { fov, mapping } => {
+                let (x, y) = (screen_x * aspect_ratio, screen_y);
+                let radius = (x * x + y * y).sqrt();
+                if radius > 1.0 {
+                    return None;
+                }
+                let theta = match mapping {
+                    FisheyeMapping::Equidistant => radius * fov / 2.0,
+                    FisheyeMapping::Stereographic => 2.0 * (radius * (fov / 4.0).tan()).atan(),
+                };
+                let phi = y.atan2(x);
+                let direction = from_camera(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos());
+                Some((self.eye, direction))
+            }
+            Projection::Cylindrical { fov_x } => {
+                let longitude = screen_x * fov_x / 2.0;
+                let height = screen_y * (fov_x / 2.0) / aspect_ratio;
+                let direction = from_camera(longitude.sin(), height, longitude.cos());
+                Some((self.eye, direction))
+            }
+        }
+    }
+
+    // Inversa de `primary_ray` en las proyecciones planas: punto de pantalla en [-1, 1] y
+    // profundidad en el eje de la vista. None detrás de la cámara y en las panorámicas.
+    pub fn project(&self, point: &Vec3, aspect_ratio: f32) -> Option<(f32, f32, f32)> {
+        if self.stereo_eye.is_some() {
+            return None;
+        }
+        let (forward, right, up) = self.basis();
+        let relative = point - self.eye;
+        let depth = relative.dot(&forward);
+
+        match self.projection {
+            Projection::Perspective { fov_y } if depth > 1e-4 => {
+                let half_height = (fov_y / 2.0).tan();
+                Some((
+                    relative.dot(&right) / (depth * aspect_ratio * half_height),
+                    relative.dot(&up) / (depth * half_height),
+                    depth,
+                ))
+            }
+            Projection::Orthographic { view_height } if depth >= 0.0 => {
+                let half_height = view_height / 2.0;
+                Some((
+                    relative.dot(&right) / (aspect_ratio * half_height),
+                    relative.dot(&up) / half_height,
+                    depth,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    // Rayo a través de la lente delgada. `lens_sample` es un punto aleatorio en [0, 1)².
+    // Todos los rayos de un pixel convergen en el plano focal, que puede estar inclinado.
+    pub fn lens_ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32, lens_sample: (f32, f32)) -> Option<(Vec3, Vec3)> {
+        let (origin, direction) = self.primary_ray(screen_x, screen_y, aspect_ratio)?;
+        // Las proyecciones panorámicas no tienen un plano de imagen para la lente
+        let planar = matches!(self.projection, Projection::Perspective { .. } | Projection::Orthographic { .. });
+        if self.aperture <= 0.0 || !planar {
+            return Some((origin, direction));
+        }
+
+        let (forward, right, up) = self.basis();
+        let focal_normal = self.focal_plane_normal();
+        let focal_point = self.eye + forward * self.focus_distance;
+
+        let denom = direction.dot(&focal_normal);
+        if denom.abs() < 1e-6 {
+            return Some((origin, direction));
+        }
+        let t = (focal_point - origin).dot(&focal_normal) / denom;
+        let focus = origin + direction * t;
+
+        let (lens_x, lens_y) = self.sample_aperture(lens_sample);
+        let lens_origin = origin + right * (lens_x * self.aperture) + up * (lens_y * self.aperture);
+        Some((lens_origin, (focus - lens_origin).normalize()))
+    }
+
+    fn focal_plane_normal(&self) -> Vec3 {
+        let (forward, right, up) = self.basis();
+        if self.tilt == 0.0 {
+            return forward;
+        }
+        let axis = right * self.tilt_rotation.cos() + up * self.tilt_rotation.sin();
+        nalgebra_glm::rotate_vec3(&forward, self.tilt, &axis)
+    }
+
+    // Punto en la apertura unitaria según su forma
+    fn sample_aperture(&self, (a, b): (f32, f32)) -> (f32, f32) {
+        match self.aperture_shape {
+            ApertureShape::Square => (2.0 * a - 1.0, 2.0 * b - 1.0),
+            ApertureShape::Circle => {
+                // Mapeo concéntrico de Shirley-Chiu: conserva la distribución uniforme
+                let (x, y) = (2.0 * a - 1.0, 2.0 * b - 1.0);
+                if x == 0.0 && y == 0.0 {
+                    return (0.0, 0.0);
+                }
+                let (r, theta) = if x.abs() > y.abs() {
+                    (x, PI / 4.0 * (y / x))
+                } else {
+                    (y, PI / 2.0 - PI / 4.0 * (x / y))
+                };
+                (r * theta.cos(), r * theta.sin())
+            }
+            ApertureShape::Polygon(blades) => {
+                let blades = blades.max(3) as f32;
+                let scaled = a * blades;
+                let index = scaled.floor();
+                let a = scaled - index;
+                let angle0 = 2.0 * PI * index / blades;
+                let angle1 = 2.0 * PI * (index + 1.0) / blades;
+                // Punto uniforme en el triángulo (centro, vértice i, vértice i + 1)
+                let root = a.sqrt();
+                let (w0, w1) = (root * (1.0 - b), root * b);
+                (
+                    w0 * angle0.cos() + w1 * angle1.cos(),
+                    w0 * angle0.sin() + w1 * angle1.sin(),
+                )
+            }
+        }
+    }
+
     pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
         let radius_vector = self.eye - self.center;
         let radius = radius_vector.magnitude();
@@ -41,6 +354,29 @@ impl Camera {
         self.eye = new_eye;
     }
 
+    // Gira la vista sobre el ojo (modo vuelo). El yaw gira a la derecha y el pitch hacia arriba.
+    pub fn look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        let view = self.center - self.eye;
+        let distance = view.magnitude();
+        let direction = view / distance;
+
+        let yaw = direction.z.atan2(direction.x) + delta_yaw;
+        let pitch = (direction.y.asin() + delta_pitch).clamp(-PI / 2.0 + 0.01, PI / 2.0 - 0.01);
+
+        let new_direction = Vec3::new(yaw.cos() * pitch.cos(), pitch.sin(), yaw.sin() * pitch.cos());
+        self.center = self.eye + new_direction * distance;
+    }
+
+    pub fn mover_arriba(&mut self, distance: f32) {
+        let up = self.up.normalize();
+        self.eye += up * distance;
+        self.center += up * distance;
+    }
+
+    pub fn mover_abajo(&mut self, distance: f32) {
+        self.mover_arriba(-distance);
+    }
+
     pub fn mover_enfrente(&mut self, distance: f32) {
         let forward = (self.center - self.eye).normalize();
         self.eye += forward * distance;
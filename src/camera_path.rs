@@ -0,0 +1,256 @@
+use nalgebra::{UnitQuaternion, Vector3};
+use std::f32::consts::PI;
+use std::fs;
+use std::io;
+use crate::camera::{Camera, Projection};
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::ray_intersect::RayIntersect;
+use crate::raytracer::{render_with_settings, RenderSettings};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    pub eye: Vector3<f32>,
+    pub center: Vector3<f32>,
+    pub up: Vector3<f32>,
+    // Campo de visión vertical en radianes
+    pub fov_y: f32,
+    // Segundos desde el inicio del recorrido
+    pub time: f32,
+}
+
+impl Keyframe {
+    pub fn from_camera(camera: &Camera, time: f32) -> Self {
+        let fov_y = match camera.projection {
+            Projection::Perspective { fov_y } => fov_y,
+            _ => PI / 2.0,
+        };
+        Keyframe {
+            eye: camera.eye,
+            center: camera.center,
+            up: camera.up,
+            fov_y,
+            time,
+        }
+    }
+
+    // Rotación que lleva +z a la dirección de vista
+    fn orientation(&self) -> UnitQuaternion<f32> {
+        UnitQuaternion::face_towards(&(self.center - self.eye), &self.up)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    // Pasa por todas las posiciones clave
+    CatmullRom,
+    // Una sola curva de Bézier que usa los puntos intermedios como control:
+    // más suave, pero solo pasa por el primero y el último
+    Bezier,
+}
+
+#[derive(Debug, Clone)]
+pub struct CameraPath {
+    pub keyframes: Vec<Keyframe>,
+    pub interpolation: Interpolation,
+}
+
+impl CameraPath {
+    pub fn new(interpolation: Interpolation) -> Self {
+        CameraPath {
+            keyframes: Vec::new(),
+            interpolation,
+        }
+    }
+
+    pub fn add_keyframe(&mut self, camera: &Camera, time: f32) {
+        self.keyframes.push(Keyframe::from_camera(camera, time));
+        self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    pub fn duration(&self) -> f32 {
+        match (self.keyframes.first(), self.keyframes.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => 0.0,
+        }
+    }
+
+    // Estado interpolado en `time` (segundos desde el primer cuadro clave)
+    pub fn sample(&self, time: f32) -> Option<Keyframe> {
+        let first = self.keyframes.first()?;
+        if self.keyframes.len() == 1 {
+            return Some(*first);
+        }
+        let time = (first.time + time).clamp(first.time, self.keyframes.last()?.time);
+
+        // Segmento que contiene `time`
+        let index = self.keyframes
+            .windows(2)
+            .position(|pair| time <= pair[1].time)
+            .unwrap_or(self.keyframes.len() - 2);
+        let (k0, k1) = (&self.keyframes[index], &self.keyframes[index + 1]);
+        let span = (k1.time - k0.time).max(1e-6);
+        let u = ((time - k0.time) / span).clamp(0.0, 1.0);
+
+        let (eye, distance, fov_y) = match self.interpolation {
+            Interpolation::CatmullRom => (
+                self.hermite(index, u, |k| k.eye),
+                self.hermite(index, u, |k| Vector3::repeat((k.center - k.eye).magnitude())).x,
+                self.hermite(index, u, |k| Vector3::repeat(k.fov_y)).x,
+            ),
+            Interpolation::Bezier => {
+                let global = (time - first.time) / self.duration().max(1e-6);
+                (
+                    self.bezier(global, |k| k.eye),
+                    self.bezier(global, |k| Vector3::repeat((k.center - k.eye).magnitude())).x,
+                    self.bezier(global, |k| Vector3::repeat(k.fov_y)).x,
+                )
+            }
+        };
+
+        // Orientación con slerp entre los cuadros del segmento
+        let (q0, q1) = (k0.orientation(), k1.orientation());
+        let rotation = q0.try_slerp(&q1, u, 1e-6).unwrap_or_else(|| q0.nlerp(&q1, u));
+
+        Some(Keyframe {
+            eye,
+            center: eye + rotation * Vector3::z() * distance,
+            up: rotation * Vector3::y(),
+            fov_y,
+            time,
+        })
+    }
+
+    pub fn apply(&self, time: f32, camera: &mut Camera) -> bool {
+        let Some(keyframe) = self.sample(time) else {
+            return false;
+        };
+        camera.eye = keyframe.eye;
+        camera.center = keyframe.center;
+        camera.up = keyframe.up;
+        if let Projection::Perspective { .. } = camera.projection {
+            camera.projection = Projection::Perspective { fov_y: keyframe.fov_y };
+        }
+        true
+    }
+
+    // Hermite cúbico con tangentes de Catmull-Rom, para tiempos no uniformes
+    fn hermite(&self, index: usize, u: f32, value: impl Fn(&Keyframe) -> Vector3<f32>) -> Vector3<f32> {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len() - 1;
+        let tangent = |i: usize| {
+            let (a, b) = (i.saturating_sub(1), (i + 1).min(last));
+            let dt = (keyframes[b].time - keyframes[a].time).max(1e-6);
+            (value(&keyframes[b]) - value(&keyframes[a])) / dt
+        };
+
+        let (p0, p1) = (value(&keyframes[index]), value(&keyframes[index + 1]));
+        let h = keyframes[index + 1].time - keyframes[index].time;
+        let (m0, m1) = (tangent(index) * h, tangent(index + 1) * h);
+
+        let u2 = u * u;
+        let u3 = u2 * u;
+        p0 * (2.0 * u3 - 3.0 * u2 + 1.0)
+            + m0 * (u3 - 2.0 * u2 + u)
+            + p1 * (-2.0 * u3 + 3.0 * u2)
+            + m1 * (u3 - u2)
+    }
+
+    // De Casteljau sobre todos los cuadros clave
+    fn bezier(&self, u: f32, value: impl Fn(&Keyframe) -> Vector3<f32>) -> Vector3<f32> {
+        let mut points: Vec<Vector3<f32>> = self.keyframes.iter().map(value).collect();
+        for level in (1..points.len()).rev() {
+            for i in 0..level {
+                points[i] = points[i].lerp(&points[i + 1], u);
+            }
+        }
+        points[0]
+    }
+
+    // Formato de texto: una línea `interpolation <catmull-rom|bezier>` y luego
+    // `time eye.xyz center.xyz up.xyz fov_y` por cuadro clave
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut contents = String::new();
+        let name = match self.interpolation {
+            Interpolation::CatmullRom => "catmull-rom",
+            Interpolation::Bezier => "bezier",
+        };
+        contents.push_str(&format!("interpolation {}\n", name));
+        for k in &self.keyframes {
+            contents.push_str(&format!(
+                "{} {} {} {} {} {} {} {} {} {} {}\n",
+                k.time, k.eye.x, k.eye.y, k.eye.z, k.center.x, k.center.y, k.center.z, k.up.x, k.up.y, k.up.z, k.fov_y
+            ));
+        }
+        fs::write(path, contents)
+    }
+
+    pub fn load(path: &str) -> io::Result<CameraPath> {
+        let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, format!("invalid camera path line: {}", line));
+        let mut camera_path = CameraPath::new(Interpolation::CatmullRom);
+
+        for line in fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("interpolation") {
+                camera_path.interpolation = match name.trim() {
+                    "catmull-rom" => Interpolation::CatmullRom,
+                    "bezier" => Interpolation::Bezier,
+                    _ => return Err(invalid(line)),
+                };
+                continue;
+            }
+
+            let values: Vec<f32> = line
+                .split_whitespace()
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid(line))?;
+            if values.len() != 11 {
+                return Err(invalid(line));
+            }
+            camera_path.keyframes.push(Keyframe {
+                time: values[0],
+                eye: Vector3::new(values[1], values[2], values[3]),
+                center: Vector3::new(values[4], values[5], values[6]),
+                up: Vector3::new(values[7], values[8], values[9]),
+                fov_y: values[10],
+            });
+        }
+
+        camera_path.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(camera_path)
+    }
+
+    // Renderiza el recorrido cuadro por cuadro a `frame_0000.png`, `frame_0001.png`, ...
+    // en un framebuffer propio de `width`×`height`
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_frames(
+        &self,
+        camera: &Camera,
+        fps: f32,
+        width: usize,
+        height: usize,
+        objects: &[Arc<dyn RayIntersect>],
+        lights: &[Light],
+        settings: &RenderSettings,
+        output_dir: &str,
+    ) -> io::Result<usize> {
+        fs::create_dir_all(output_dir)?;
+        let frames = (self.duration() * fps).floor() as usize + 1;
+        let mut framebuffer = Framebuffer::new(width, height);
+        let mut frame_camera = camera.clone();
+
+        for frame in 0..frames {
+            self.apply(frame as f32 / fps, &mut frame_camera);
+            render_with_settings(&mut framebuffer, objects, &frame_camera, lights, settings);
+            let path = format!("{}/frame_{:04}.png", output_dir, frame);
+            framebuffer.save(&path).map_err(io::Error::other)?;
+        }
+
+        Ok(frames)
+    }
+}
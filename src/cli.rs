@@ -0,0 +1,380 @@
+use nalgebra::Vector3;
+use std::time::Instant;
+use crate::camera::Camera;
+use crate::aov::{AovView, Aovs};
+use crate::denoise::Denoiser;
+use crate::exr::ExrImage;
+use crate::framebuffer::{write_pfm, Framebuffer, HdrFramebuffer};
+use crate::raytracer::{render_hdr, render_with_aovs, RenderSettings};
+use crate::sampling::{Adaptive, Filter, SamplePattern};
+use crate::scene::Scene;
+use crate::tiles::TileOrder;
+use crate::upscale::Upscaler;
+
+pub const USAGE: &str = "\
+Uso: MinecraftDioramaRaytracing [opciones]
+
+Opciones:
+  --headless                 renderiza una imagen sin abrir la ventana
+  --scene <archivo>          escena de texto (por defecto el diorama)
+  --camera ex,ey,ez,cx,cy,cz posición de la cámara y punto al que mira
+  --width <pixeles>          ancho de la imagen (800)
+  --height <pixeles>         alto de la imagen (600)
+  --spp <n>                  muestras por pixel (1)
+  --sampling <patrón>        random, stratified (rejilla N×N) o jittered (rejilla con
+                             azar); por defecto stratified con 1 muestra y si no jittered
+  --filter <filtro>          box, tent, gaussian o mitchell (box)
+  --adaptive <umbral>        muestras extra donde el contraste local supera el umbral (0 a 1)
+  --adaptive-spp <n>         muestras extra por pixel refinado (16)
+  --denoise                  filtra el ruido guiado por profundidad, normales y albedo
+  --output <archivo>         imagen de salida, el formato sale de la extensión;
+                             .exr y .pfm guardan color lineal sin recortar (render.png)
+  --aovs                     guarda también profundidad, normales, albedo, UV e ids de
+                             objeto y material: como capas en .exr y en archivos
+                             aparte (render_depth.png, ...) en los demás formatos
+  --target-ms <ms>           tiempo por cuadro que busca la ventana al moverse (33)
+  --fps <n>                  cuadros por segundo con que se refresca la ventana (60)
+  --scale <factor>           resolución del render respecto de la ventana, que estira la
+                             imagen al mostrarla (1)
+  --fullscreen               abre la ventana sin bordes; F11 la alterna
+  --config <archivo>         opciones por defecto, una `nombre = valor` por línea; las de
+                             la línea de comandos tienen prioridad (diorama.conf si existe)
+  --upscale <filtro>         cómo se agrandan los cuadros en movimiento: nearest, bilinear,
+                             bicubic, lanczos o depth (guiado por profundidad y normales)
+  --tile-order <orden>       orden de los tiles de la imagen quieta: spiral (desde el
+                             centro) o morton (curva Z)
+  --help                     muestra esta ayuda";
+
+// Se carga sola si existe y no se pasó --config
+pub const CONFIG_FILE: &str = "diorama.conf";
+
+// Pasadas que se exportan con --aovs
+const AOV_PASSES: [AovView; 6] = [
+    AovView::Depth,
+    AovView::Normal,
+    AovView::Albedo,
+    AovView::Uv,
+    AovView::ObjectId,
+    AovView::MaterialId,
+];
+
+pub struct Options {
+    pub headless: bool,
+    pub scene: Option<String>,
+    // (eye, center)
+    pub camera: Option<(Vector3<f32>, Vector3<f32>)>,
+    pub width: usize,
+    pub height: usize,
+    pub samples_per_pixel: u32,
+    pub pattern: Option<SamplePattern>,
+    pub filter: Filter,
+    pub adaptive_threshold: Option<f32>,
+    pub adaptive_samples: u32,
+    pub denoise: bool,
+    pub output: String,
+    pub aovs: bool,
+    pub target_frame_ms: f32,
+    pub fps: usize,
+    // Pixeles renderizados por pixel de la ventana
+    pub scale: f32,
+    pub fullscreen: bool,
+    pub upscaler: Upscaler,
+    pub tile_order: TileOrder,
+    pub help: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            headless: false,
+            scene: None,
+            camera: None,
+            width: 800,
+            height: 600,
+            samples_per_pixel: 1,
+            pattern: None,
+            filter: Filter::Box,
+            adaptive_threshold: None,
+            adaptive_samples: 16,
+            denoise: false,
+            output: "render.png".to_string(),
+            aovs: false,
+            target_frame_ms: 33.0,
+            fps: 60,
+            scale: 1.0,
+            fullscreen: false,
+            upscaler: Upscaler::DepthGuided,
+            tile_order: TileOrder::Spiral,
+            help: false,
+        }
+    }
+}
+
+impl Options {
+    // Lee los argumentos sin el nombre del programa. Las opciones del archivo de
+    // configuración van primero, así las de la línea de comandos las pisan.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let args: Vec<String> = args.into_iter().collect();
+        let config = match args.iter().position(|arg| arg == "--config") {
+            Some(index) => Some(args.get(index + 1).ok_or("--config necesita un valor")?.clone()),
+            None => std::path::Path::new(CONFIG_FILE).exists().then(|| CONFIG_FILE.to_string()),
+        };
+        let mut all_args = match config {
+            Some(path) => config_args(&path)?,
+            None => Vec::new(),
+        };
+        all_args.extend(args);
+
+        let mut options = Options::default();
+        let mut args = all_args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or_else(|| format!("{} necesita un valor", name));
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--aovs" => options.aovs = true,
+                "--denoise" => options.denoise = true,
+                "--fullscreen" => options.fullscreen = true,
+                "--help" | "-h" => options.help = true,
+                "--scene" => options.scene = Some(value("--scene")?),
+                "--camera" => options.camera = Some(parse_camera(&value("--camera")?)?),
+                "--width" => options.width = parse_positive(&value("--width")?, "--width")?,
+                "--height" => options.height = parse_positive(&value("--height")?, "--height")?,
+                "--spp" => options.samples_per_pixel = parse_positive(&value("--spp")?, "--spp")? as u32,
+                "--sampling" => {
+                    let name = value("--sampling")?;
+                    options.pattern = Some(SamplePattern::from_name(&name).ok_or_else(|| format!("patrón de muestreo desconocido `{}`", name))?);
+                }
+                "--filter" => {
+                    let name = value("--filter")?;
+                    options.filter = Filter::from_name(&name).ok_or_else(|| format!("filtro desconocido `{}`", name))?;
+                }
+                "--adaptive" => {
+                    let threshold = value("--adaptive")?;
+                    options.adaptive_threshold = Some(threshold.parse::<f32>().map_err(|_| format!("--adaptive espera un número, se recibió `{}`", threshold))?);
+                }
+                "--adaptive-spp" => options.adaptive_samples = parse_positive(&value("--adaptive-spp")?, "--adaptive-spp")? as u32,
+                "--target-ms" => {
+                    let target = value("--target-ms")?;
+                    options.target_frame_ms = match target.parse::<f32>() {
+                        Ok(ms) if ms > 0.0 => ms,
+                        _ => return Err(format!("--target-ms espera un número positivo, se recibió `{}`", target)),
+                    };
+                }
+                "--config" => {
+                    value("--config")?;
+                }
+                "--scale" => {
+                    let scale = value("--scale")?;
+                    options.scale = match scale.parse::<f32>() {
+                        Ok(scale) if scale > 0.0 && scale <= 4.0 => scale,
+                        _ => return Err(format!("--scale espera un número entre 0 y 4, se recibió `{}`", scale)),
+                    };
+                }
+                "--fps" => options.fps = parse_positive(&value("--fps")?, "--fps")?,
+                "--upscale" => {
+                    let name = value("--upscale")?;
+                    options.upscaler = Upscaler::from_name(&name).ok_or_else(|| format!("filtro de escalado desconocido `{}`", name))?;
+                }
+                "--tile-order" => {
+                    let name = value("--tile-order")?;
+                    options.tile_order = TileOrder::from_name(&name).ok_or_else(|| format!("orden de tiles desconocido `{}`", name))?;
+                }
+                "--output" | "-o" => options.output = value("--output")?,
+                _ => return Err(format!("opción desconocida `{}`", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+
+    pub fn render_settings(&self) -> RenderSettings {
+        let default_pattern = if self.samples_per_pixel > 1 { SamplePattern::Jittered } else { SamplePattern::Stratified };
+        RenderSettings {
+            samples_per_pixel: self.samples_per_pixel,
+            pattern: self.pattern.unwrap_or(default_pattern),
+            filter: self.filter,
+            adaptive: self.adaptive_threshold.map(|threshold| Adaptive {
+                threshold,
+                extra_samples: self.adaptive_samples,
+            }),
+            soft_shadows: false,
+        }
+    }
+
+    pub fn load_scene(&self) -> Result<Scene, String> {
+        let mut scene = match &self.scene {
+            Some(path) => Scene::load(path)?,
+            None => Scene::diorama()?,
+        };
+        if let Some((eye, center)) = self.camera {
+            scene.camera = Camera::new(eye, center, Vector3::new(0.0, 1.0, 0.0));
+        }
+        Ok(scene)
+    }
+}
+
+// Pasa cada línea `nombre = valor` del archivo a `--nombre valor`. Las opciones sin valor
+// se escriben solas o con `= true`; `# ...` es un comentario.
+fn config_args(path: &str) -> Result<Vec<String>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("no se pudo leer {}: {}", path, e))?;
+    let mut args = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (name, value) = match line.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (line, None),
+        };
+        if name.is_empty() || name.starts_with('-') || name.contains(char::is_whitespace) || name == "config" {
+            return Err(format!("{}:{}: línea inválida `{}`", path, number + 1, line));
+        }
+        match value {
+            Some("false") => {}
+            None | Some("true") => args.push(format!("--{}", name)),
+            Some(value) => args.extend([format!("--{}", name), value.to_string()]),
+        }
+    }
+    Ok(args)
+}
+
+fn parse_positive(value: &str, name: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!("{} espera un entero positivo, se recibió `{}`", name, value)),
+    }
+}
+
+fn parse_camera(value: &str) -> Result<(Vector3<f32>, Vector3<f32>), String> {
+    let numbers: Vec<f32> = value
+        .split(',')
+        .map(|number| number.trim().parse::<f32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("--camera espera seis números separados por comas, se recibió `{}`", value))?;
+    if numbers.len() != 6 {
+        return Err(format!("--camera espera seis números separados por comas, se recibió `{}`", value));
+    }
+    Ok((
+        Vector3::new(numbers[0], numbers[1], numbers[2]),
+        Vector3::new(numbers[3], numbers[4], numbers[5]),
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    // Lo que `image` sepa escribir, en 8 bits
+    Ldr,
+    Exr,
+    Pfm,
+}
+
+fn output_format(path: &str) -> OutputFormat {
+    let extension = std::path::Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("exr") => OutputFormat::Exr,
+        Some("pfm") => OutputFormat::Pfm,
+        _ => OutputFormat::Ldr,
+    }
+}
+
+fn denoise_hdr(options: &Options, framebuffer: &mut HdrFramebuffer, aovs: &Option<Aovs>) {
+    if let (true, Some(aovs)) = (options.denoise, aovs) {
+        Denoiser::default().apply_hdr(framebuffer, aovs);
+    }
+}
+
+// render.png -> render_depth.png
+fn pass_path(output: &str, view: AovView) -> String {
+    let path = std::path::Path::new(output);
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("render");
+    let name = match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => format!("{}_{}.{}", stem, view.name(), extension),
+        None => format!("{}_{}", stem, view.name()),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+// Renderiza una sola imagen y la guarda. Devuelve el código de salida del proceso:
+// 0 si todo salió bien y 1 si falló la escena o la escritura.
+pub fn run_headless(options: &Options) -> i32 {
+    let start = Instant::now();
+    let scene = match options.load_scene() {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return 1;
+        }
+    };
+    let load_time = start.elapsed();
+
+    let settings = options.render_settings();
+    let render_start = Instant::now();
+    let format = output_format(&options.output);
+    // El denoiser necesita las pasadas aunque no se vayan a guardar
+    let mut aovs = (options.aovs || options.denoise).then(|| Aovs::new(options.width, options.height));
+    let saved = match format {
+        OutputFormat::Exr => {
+            let mut framebuffer = HdrFramebuffer::new(options.width, options.height);
+            render_hdr(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, &settings, aovs.as_mut());
+            denoise_hdr(options, &mut framebuffer, &aovs);
+            let mut image = ExrImage::new(options.width, options.height);
+            image.add_rgb(None, &framebuffer.to_linear());
+            if let (true, Some(aovs)) = (options.aovs, &aovs) {
+                aovs.add_to_exr(&mut image);
+            }
+            image.save(&options.output).map_err(|e| e.to_string())
+        }
+        OutputFormat::Pfm => {
+            let mut framebuffer = HdrFramebuffer::new(options.width, options.height);
+            render_hdr(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, &settings, aovs.as_mut());
+            denoise_hdr(options, &mut framebuffer, &aovs);
+            framebuffer.save_pfm(&options.output).map_err(|e| e.to_string())
+        }
+        OutputFormat::Ldr => {
+            let mut framebuffer = Framebuffer::new(options.width, options.height);
+            render_with_aovs(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, &settings, aovs.as_mut());
+            if let (true, Some(aovs)) = (options.denoise, &aovs) {
+                Denoiser::default().apply(&mut framebuffer, aovs);
+            }
+            framebuffer.save(&options.output).map_err(|e| e.to_string())
+        }
+    };
+    // En EXR las pasadas ya van como capas del mismo archivo
+    let saved = match (&aovs, format) {
+        _ if !options.aovs => saved,
+        (Some(aovs), OutputFormat::Pfm) => saved.and_then(|()| {
+            AOV_PASSES.iter().try_for_each(|&view| {
+                write_pfm(&pass_path(&options.output, view), aovs.width, aovs.height, &aovs.channel(view)).map_err(|e| e.to_string())
+            })
+        }),
+        (Some(aovs), OutputFormat::Ldr) => saved.and_then(|()| {
+            AOV_PASSES.iter().try_for_each(|&view| {
+                aovs.visualize(view).save(&pass_path(&options.output, view)).map_err(|e| e.to_string())
+            })
+        }),
+        _ => saved,
+    };
+    let render_time = render_start.elapsed();
+
+    if let Err(e) = saved {
+        eprintln!("error: no se pudo guardar {}: {}", options.output, e);
+        return 1;
+    }
+
+    println!(
+        "{} ({}x{}, {} spp): escena {:.2?}, render {:.2?}, total {:.2?}",
+        options.output,
+        options.width,
+        options.height,
+        settings.samples_per_pixel,
+        load_time,
+        render_time,
+        start.elapsed()
+    );
+    0
+}
@@ -12,14 +12,6 @@ impl Color {
         Color { r, g, b }
     }
 
-    pub fn scale(&self, factor: f32) -> Self {
-        Color {
-            r: (self.r as f32 * factor).min(255.0) as u8,
-            g: (self.g as f32 * factor).min(255.0) as u8,
-            b: (self.b as f32 * factor).min(255.0) as u8,
-        }
-    }
-
     pub fn to_u32(&self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
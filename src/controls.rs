@@ -0,0 +1,203 @@
+use minifb::{Key, MouseMode, Window};
+use nalgebra::Vector3;
+use crate::camera::Camera;
+use crate::physics::{self, Player, EYE_HEIGHT};
+use crate::ray_intersect::RayIntersect;
+use std::sync::Arc;
+
+// Tecla del modo vuelo y el movimiento de la cámara que hace
+type Movement = (Key, fn(&mut Camera, f32));
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControlMode {
+    // WASD desplaza y las flechas orbitan alrededor de `center`
+    Orbit,
+    // Primera persona volando: el ratón gira la vista
+    Fly,
+    // Como el jugador: gravedad, colisiones con los bloques, escalones y salto
+    Walk,
+}
+
+pub struct CameraController {
+    pub mode: ControlMode,
+    // Unidades por segundo en modo vuelo
+    pub speed: f32,
+    pub sprint_multiplier: f32,
+    // Radianes por pixel de movimiento del ratón
+    pub mouse_sensitivity: f32,
+    // Bloques por segundo al caminar
+    pub walk_speed: f32,
+    last_mouse: Option<(f32, f32)>,
+    player: Option<Player>,
+}
+
+impl CameraController {
+    pub fn new() -> Self {
+        CameraController {
+            mode: ControlMode::Orbit,
+            speed: 6.0,
+            sprint_multiplier: 2.5,
+            mouse_sensitivity: 0.004,
+            walk_speed: 4.3,
+            last_mouse: None,
+            player: None,
+        }
+    }
+
+    pub fn next_mode(&mut self, window: &mut Window) {
+        self.mode = match self.mode {
+            ControlMode::Orbit => ControlMode::Fly,
+            ControlMode::Fly => ControlMode::Walk,
+            ControlMode::Walk => ControlMode::Orbit,
+        };
+        self.last_mouse = None;
+        self.player = None;
+        window.set_cursor_visibility(self.mode == ControlMode::Orbit);
+    }
+
+    // Aplica la entrada de este cuadro a la cámara. Devuelve true si se movió.
+    pub fn update(&mut self, window: &Window, camera: &mut Camera, objects: &[Arc<dyn RayIntersect>], dt: f32) -> bool {
+        match self.mode {
+            ControlMode::Orbit => self.update_orbit(window, camera),
+            ControlMode::Fly => self.update_fly(window, camera, dt),
+            ControlMode::Walk => self.update_walk(window, camera, objects, dt),
+        }
+    }
+
+    fn update_orbit(&mut self, window: &Window, camera: &mut Camera) -> bool {
+        let mut camera_moved = false;
+
+        if window.is_key_down(Key::W) {
+            camera.mover_enfrente(0.4);
+            camera_moved = true;
+        }
+        if window.is_key_down(Key::S) {
+            camera.mover_atras(0.4);
+            camera_moved = true;
+        }
+        if window.is_key_down(Key::A) {
+            camera.mover_izq(0.4);
+            camera_moved = true;
+        }
+        if window.is_key_down(Key::D) {
+            camera.mover_der(0.4);
+            camera_moved = true;
+        }
+
+        if window.is_key_down(Key::Up) {
+            camera.orbit(0.0, -0.1);
+            camera_moved = true;
+        }
+        if window.is_key_down(Key::Down) {
+            camera.orbit(0.0, 0.1);
+            camera_moved = true;
+        }
+        if window.is_key_down(Key::Left) {
+            camera.orbit(-0.1, 0.0);
+            camera_moved = true;
+        }
+        if window.is_key_down(Key::Right) {
+            camera.orbit(0.1, 0.0);
+            camera_moved = true;
+        }
+
+        camera_moved
+    }
+
+    fn mouse_look(&mut self, window: &Window, camera: &mut Camera) -> bool {
+        let mut camera_moved = false;
+        if let Some((mouse_x, mouse_y)) = window.get_mouse_pos(MouseMode::Pass) {
+            if let Some((last_x, last_y)) = self.last_mouse {
+                let (dx, dy) = (mouse_x - last_x, mouse_y - last_y);
+                if dx != 0.0 || dy != 0.0 {
+                    camera.look(dx * self.mouse_sensitivity, -dy * self.mouse_sensitivity);
+                    camera_moved = true;
+                }
+            }
+            self.last_mouse = Some((mouse_x, mouse_y));
+        }
+        camera_moved
+    }
+
+    fn update_fly(&mut self, window: &Window, camera: &mut Camera, dt: f32) -> bool {
+        let mut camera_moved = self.mouse_look(window, camera);
+
+        // La rueda cambia la velocidad de vuelo
+        if let Some((_, scroll)) = window.get_scroll_wheel() {
+            if scroll != 0.0 {
+                self.speed = (self.speed * 1.1_f32.powf(scroll.signum())).clamp(0.5, 100.0);
+            }
+        }
+
+        let mut step = self.speed * dt;
+        if window.is_key_down(Key::LeftCtrl) {
+            step *= self.sprint_multiplier;
+        }
+
+        let movements: [Movement; 6] = [
+            (Key::W, Camera::mover_enfrente),
+            (Key::S, Camera::mover_atras),
+            (Key::A, Camera::mover_izq),
+            (Key::D, Camera::mover_der),
+            (Key::Space, Camera::mover_arriba),
+            (Key::LeftShift, Camera::mover_abajo),
+        ];
+        for (key, movement) in movements {
+            if window.is_key_down(key) {
+                movement(camera, step);
+                camera_moved = true;
+            }
+        }
+
+        camera_moved
+    }
+
+    fn update_walk(&mut self, window: &Window, camera: &mut Camera, objects: &[Arc<dyn RayIntersect>], dt: f32) -> bool {
+        let colliders = physics::colliders(objects);
+
+        let player = self.player.get_or_insert_with(|| {
+            // Aparece sobre el suelo bajo la cámara o, si no hay, bajo el punto al que mira
+            let spawn = [camera.eye, camera.center]
+                .iter()
+                .find_map(|point| {
+                    physics::ground_height(point.x, point.z, &colliders).map(|y| Vector3::new(point.x, y + 0.01, point.z))
+                })
+                .unwrap_or(camera.eye - Vector3::new(0.0, EYE_HEIGHT, 0.0));
+            Player::new(spawn)
+        });
+        let previous_position = player.position;
+
+        let (forward, right, _) = camera.basis();
+        let forward = Vector3::new(forward.x, 0.0, forward.z).try_normalize(1e-6).unwrap_or_else(Vector3::zeros);
+        let right = Vector3::new(right.x, 0.0, right.z).try_normalize(1e-6).unwrap_or_else(Vector3::zeros);
+
+        let mut wish = Vector3::zeros();
+        if window.is_key_down(Key::W) {
+            wish += forward;
+        }
+        if window.is_key_down(Key::S) {
+            wish -= forward;
+        }
+        if window.is_key_down(Key::D) {
+            wish += right;
+        }
+        if window.is_key_down(Key::A) {
+            wish -= right;
+        }
+        let mut speed = self.walk_speed;
+        if window.is_key_down(Key::LeftCtrl) {
+            speed *= 1.3;
+        }
+        let wish = wish.try_normalize(1e-6).unwrap_or_else(Vector3::zeros) * speed;
+
+        player.update(wish, window.is_key_down(Key::Space), dt, &colliders);
+
+        let view = camera.center - camera.eye;
+        camera.eye = player.eye();
+        camera.center = camera.eye + view;
+
+        let moved = (player.position - previous_position).magnitude() > 1e-5;
+        let looked = self.mouse_look(window, camera);
+        moved || looked
+    }
+}
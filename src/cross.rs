@@ -0,0 +1,165 @@
+use nalgebra_glm::Vec3;
+use crate::ray_intersect::{Intersect, RayIntersect, Material};
+use std::any::Any;
+use crate::aabb::Aabb;
+use std::f32::consts::FRAC_1_SQRT_2;
+
+// Dirección horizontal del viento (normalizada)
+const WIND: [f32; 2] = [0.894_427, 0.447_213];
+const SWAY_SPEED: f32 = 1.7;
+
+// Dos planos con textura que se cruzan en diagonal, como las flores,
+// el pasto alto y los brotes de Minecraft. Los pixeles transparentes
+// de la textura se descartan con prueba alfa.
+#[derive(Clone)]
+pub struct CrossBlock {
+    pub center: Vec3,
+    pub size: f32,
+    pub material: Material,
+    pub offset: Vec3,
+    pub sway: f32,
+    pub time: f32,
+    phase: f32,
+}
+
+impl CrossBlock {
+    pub fn new(center: Vec3, size: f32, material: Material) -> Self {
+        let hash = position_hash(&center, size);
+        CrossBlock {
+            center,
+            size,
+            material,
+            offset: Vec3::zeros(),
+            sway: 0.0,
+            time: 0.0,
+            phase: (hash & 0xFFFF) as f32 / 65535.0 * std::f32::consts::TAU,
+        }
+    }
+
+    // Desplazamiento pseudoaleatorio que depende solo de la posición del bloque,
+    // así cada planta cae siempre en el mismo lugar
+    pub fn with_random_offset(mut self) -> Self {
+        let hash = position_hash(&self.center, self.size);
+        let random = |shift: u32| ((hash >> shift) & 0xF) as f32 / 15.0;
+        self.offset = Vec3::new(
+            (random(16) - 0.5) * 0.5 * self.size,
+            -random(24) * 0.2 * self.size,
+            (random(32) - 0.5) * 0.5 * self.size,
+        );
+        self
+    }
+
+    pub fn with_sway(mut self, amplitude: f32) -> Self {
+        self.sway = amplitude;
+        self
+    }
+
+    // Inclinación de la punta en este instante; la base no se mueve
+    fn shear(&self) -> f32 {
+        self.sway * (self.time * SWAY_SPEED + self.phase).sin()
+    }
+
+    // Pasa un punto o vector al espacio de la planta sin viento
+    fn unshear(&self, vector: &Vec3, height: f32, shear: f32) -> Vec3 {
+        let bend = shear * height;
+        Vec3::new(vector.x - WIND[0] * bend, vector.y, vector.z - WIND[1] * bend)
+    }
+}
+
+impl RayIntersect for CrossBlock {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        let mitad = self.size / 2.0;
+        let base = self.center + self.offset;
+        let shear = self.shear();
+
+        let relative_origin = ray_origin - base;
+        let local_origin = self.unshear(&relative_origin, (relative_origin.y + mitad) / self.size, shear);
+        let local_direction = self.unshear(ray_direction, ray_direction.y / self.size, shear);
+
+        let planes = [
+            (Vec3::new(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2), Vec3::new(FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2)),
+            (Vec3::new(FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2), Vec3::new(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2)),
+        ];
+
+        let mut closest: Option<(f32, Vec3, f32, f32)> = None;
+
+        for (normal, tangent) in planes {
+            let denom = local_direction.dot(&normal);
+            if denom.abs() < 1e-6 {
+                continue;
+            }
+            let t = -local_origin.dot(&normal) / denom;
+            if t <= 0.0 || closest.is_some_and(|(best, _, _, _)| t >= best) {
+                continue;
+            }
+
+            let local_point = local_origin + local_direction * t;
+            if local_point.x.abs() > mitad || local_point.z.abs() > mitad || local_point.y.abs() > mitad {
+                continue;
+            }
+
+            let u = (local_point.dot(&tangent) / (mitad * 2.0_f32.sqrt()) + 1.0) / 2.0;
+            let v = 1.0 - (local_point.y + mitad) / self.size;
+            let (u, v) = (u.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+            if self.material.is_transparent_at(u, v) {
+                continue;
+            }
+
+            closest = Some((t, normal, u, v));
+        }
+
+        let Some((t, local_normal, u, v)) = closest else {
+            return Intersect::empty();
+        };
+
+        // La normal se transforma con la inversa transpuesta del corte
+        let wind_dot = WIND[0] * local_normal.x + WIND[1] * local_normal.z;
+        let mut normal = Vec3::new(local_normal.x, local_normal.y - shear / self.size * wind_dot, local_normal.z).normalize();
+        // Los planos se ven por ambos lados
+        if normal.dot(ray_direction) > 0.0 {
+            normal = -normal;
+        }
+
+        Intersect::new(
+            ray_origin + ray_direction * t,
+            normal,
+            t,
+            self.material.clone(),
+            u,
+            v,
+        )
+    }
+
+    fn get_uv(&self, point: &Vec3) -> (f32, f32) {
+        let mitad = self.size / 2.0;
+        let relative = point - (self.center + self.offset);
+        let local = self.unshear(&relative, (relative.y + mitad) / self.size, self.shear());
+        let along_first = (local.x + local.z) * FRAC_1_SQRT_2;
+        let along_second = (local.x - local.z) * FRAC_1_SQRT_2;
+        let along = if along_first.abs() > along_second.abs() { along_first } else { along_second };
+        let u = (along / (mitad * 2.0_f32.sqrt()) + 1.0) / 2.0;
+        let v = 1.0 - (local.y + mitad) / self.size;
+        (u.clamp(0.0, 1.0), v.clamp(0.0, 1.0))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::from_center_size(self.center, self.size))
+    }
+}
+
+fn position_hash(center: &Vec3, size: f32) -> u64 {
+    let x = (center.x / size).floor() as i64;
+    let y = (center.y / size).floor() as i64;
+    let z = (center.z / size).floor() as i64;
+    let mut hash = (x.wrapping_mul(3_129_871) ^ z.wrapping_mul(116_129_781) ^ y) as u64;
+    hash = hash.wrapping_mul(hash.wrapping_mul(42_317_861).wrapping_add(11));
+    hash ^ (hash >> 29)
+}
@@ -4,22 +4,28 @@ use image::RgbaImage;
 use crate::color::Color;
 use crate::light::Light; 
 use std::any::Any;
+use crate::aabb::Aabb;
 
+#[derive(Clone)]
 pub struct Cube {
+    // Posición de reposo, la que define el bloque que ocupa
     pub center: Vec3,
     pub size: f32,
     pub materials: [Material; 6],
+    // Desplazamiento que se ve respecto de `center`; lo usa la animación del agua
+    pub offset: Vec3,
 }
 
 impl Cube {
     pub fn new(center: Vec3, size: f32, materials: [Material; 6]) -> Self {
-        Cube { center, size, materials }
+        Cube { center, size, materials, offset: Vec3::zeros() }
     }
 
     fn get_uv(&self, punto_encuentro: &Vec3) -> (f32, f32) {
         let mitad = self.size / 2.0;
-        let min = self.center - Vec3::new(mitad, mitad, mitad);
-        let max = self.center + Vec3::new(mitad, mitad, mitad);
+        let center = self.center + self.offset;
+        let min = center - Vec3::new(mitad, mitad, mitad);
+        let max = center + Vec3::new(mitad, mitad, mitad);
 
         let mut u = 0.0;
         let mut v = 0.0;
@@ -67,8 +73,9 @@ impl Cube {
 impl RayIntersect for Cube {
     fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
         let mitad = self.size / 2.0;
-        let min = self.center - Vec3::new(mitad, mitad, mitad);
-        let max = self.center + Vec3::new(mitad, mitad, mitad);
+        let center = self.center + self.offset;
+        let min = center - Vec3::new(mitad, mitad, mitad);
+        let max = center + Vec3::new(mitad, mitad, mitad);
 
         let inv_dir = Vec3::new(1.0 / ray_direction.x, 1.0 / ray_direction.y, 1.0 / ray_direction.z);
         let t_min = (min - ray_origin).component_mul(&inv_dir);
@@ -125,8 +132,9 @@ impl RayIntersect for Cube {
 
     fn get_uv(&self, punto_encuentro: &Vec3) -> (f32, f32) {
         let mitad = self.size / 2.0;
-        let min = self.center - Vec3::new(mitad, mitad, mitad);
-        let max = self.center + Vec3::new(mitad, mitad, mitad);
+        let center = self.center + self.offset;
+        let min = center - Vec3::new(mitad, mitad, mitad);
+        let max = center + Vec3::new(mitad, mitad, mitad);
 
         let mut u = 0.0;
         let mut v = 0.0;
@@ -151,7 +159,24 @@ impl RayIntersect for Cube {
         (u, v)
     }
 
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
+
+    fn collision_boxes(&self) -> Vec<Aabb> {
+        if self.materials.iter().any(|material| material.solid) {
+            vec![Aabb::from_center_size(self.center, self.size)]
+        } else {
+            Vec::new()
+        }
+    }
+
+    // El bloque en reposo: el desplazamiento de la animación no lo cambia de lugar en la grilla
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::from_center_size(self.center, self.size))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,147 @@
+use rayon::prelude::*;
+use crate::aov::Aovs;
+use crate::framebuffer::{Framebuffer, HdrFramebuffer};
+
+// Coeficientes del spline B3 del filtro À-trous (5×5 separable)
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+// Filtro À-trous que evita los bordes (Dammertz et al. 2010). Cada pasada agranda el
+// salto entre muestras y las pesa por parecido en color, normal y profundidad, así
+// que no mezcla bloques distintos. El color se divide por el albedo antes de filtrar
+// y se vuelve a multiplicar al final, para no borrar el detalle de las texturas.
+#[derive(Debug, Clone, Copy)]
+pub struct Denoiser {
+    pub iterations: u32,
+    // Diferencia de color (0 a 1) que todavía se considera ruido
+    pub sigma_color: f32,
+    // Exponente sobre el coseno entre normales
+    pub sigma_normal: f32,
+    // Diferencia de profundidad relativa tolerada
+    pub sigma_depth: f32,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Denoiser {
+            iterations: 5,
+            sigma_color: 0.6,
+            sigma_normal: 128.0,
+            sigma_depth: 0.05,
+        }
+    }
+}
+
+impl Denoiser {
+    // Misma configuración con la tolerancia de color multiplicada por `factor`
+    pub fn with_strength(mut self, factor: f32) -> Self {
+        self.sigma_color *= factor;
+        self
+    }
+
+    pub fn apply(&self, framebuffer: &mut Framebuffer, aovs: &Aovs) {
+        let color: Vec<[f32; 3]> = framebuffer
+            .buffer
+            .iter()
+            .map(|&pixel| [(pixel >> 16) & 0xFF, (pixel >> 8) & 0xFF, pixel & 0xFF].map(|channel| channel as f32 / 255.0))
+            .collect();
+        let filtered = self.denoise(&color, aovs);
+        for (pixel, color) in framebuffer.buffer.iter_mut().zip(filtered) {
+            let [r, g, b] = color.map(|channel| (channel * 255.0).round().clamp(0.0, 255.0) as u32);
+            *pixel = (r << 16) | (g << 8) | b;
+        }
+    }
+
+    pub fn apply_hdr(&self, framebuffer: &mut HdrFramebuffer, aovs: &Aovs) {
+        framebuffer.buffer = self.denoise(&framebuffer.buffer, aovs);
+    }
+
+    pub fn denoise(&self, color: &[[f32; 3]], aovs: &Aovs) -> Vec<[f32; 3]> {
+        let (width, height) = (aovs.width, aovs.height);
+        assert_eq!(color.len(), width * height, "las pasadas auxiliares deben tener el tamaño de la imagen");
+
+        // Iluminación sin textura
+        let albedo: Vec<[f32; 3]> = aovs.pixels.iter().map(|pixel| pixel.albedo.map(|channel| channel.max(0.02))).collect();
+        let mut irradiance: Vec<[f32; 3]> = color
+            .iter()
+            .zip(&albedo)
+            .map(|(color, albedo)| [color[0] / albedo[0], color[1] / albedo[1], color[2] / albedo[2]])
+            .collect();
+
+        for iteration in 0..self.iterations {
+            let step = 1usize << iteration;
+            // Las pasadas más anchas toleran menos diferencia de color
+            let sigma_color = self.sigma_color / (1u32 << iteration) as f32;
+            irradiance = (0..width * height)
+                .into_par_iter()
+                .map(|index| self.filter_pixel(index, step, sigma_color, &irradiance, aovs))
+                .collect();
+        }
+
+        irradiance
+            .iter()
+            .zip(&albedo)
+            .zip(color)
+            .zip(&aovs.pixels)
+            .map(|(((irradiance, albedo), original), aov)| {
+                // El fondo no tiene datos auxiliares; se deja como estaba
+                if aov.object_id == 0 {
+                    *original
+                } else {
+                    [irradiance[0] * albedo[0], irradiance[1] * albedo[1], irradiance[2] * albedo[2]]
+                }
+            })
+            .collect()
+    }
+
+    fn filter_pixel(&self, index: usize, step: usize, sigma_color: f32, irradiance: &[[f32; 3]], aovs: &Aovs) -> [f32; 3] {
+        let (width, height) = (aovs.width as isize, aovs.height as isize);
+        let center = &aovs.pixels[index];
+        let center_color = irradiance[index];
+        if center.object_id == 0 {
+            return center_color;
+        }
+
+        let (x, y) = ((index % aovs.width) as isize, (index / aovs.width) as isize);
+        let mut sum = [0.0; 3];
+        let mut weight_sum = 0.0;
+
+        for (j, kernel_y) in KERNEL.iter().enumerate() {
+            let sample_y = y + (j as isize - 2) * step as isize;
+            if sample_y < 0 || sample_y >= height {
+                continue;
+            }
+            for (i, kernel_x) in KERNEL.iter().enumerate() {
+                let sample_x = x + (i as isize - 2) * step as isize;
+                if sample_x < 0 || sample_x >= width {
+                    continue;
+                }
+                let sample_index = (sample_y * width + sample_x) as usize;
+                let sample = &aovs.pixels[sample_index];
+                if sample.object_id == 0 {
+                    continue;
+                }
+                let sample_color = irradiance[sample_index];
+
+                let color_distance: f32 = (0..3).map(|c| (center_color[c] - sample_color[c]).powi(2)).sum();
+                let color_weight = (-color_distance / (sigma_color * sigma_color).max(1e-8)).exp();
+
+                let cosine: f32 = (0..3).map(|c| center.normal[c] * sample.normal[c]).sum();
+                let normal_weight = cosine.clamp(0.0, 1.0).powf(self.sigma_normal);
+
+                let depth_difference = (center.depth - sample.depth).abs() / center.depth.max(1e-3);
+                let depth_weight = (-depth_difference / (self.sigma_depth * step as f32)).exp();
+
+                let weight = kernel_x * kernel_y * color_weight * normal_weight * depth_weight;
+                for (total, channel) in sum.iter_mut().zip(sample_color) {
+                    *total += channel * weight;
+                }
+                weight_sum += weight;
+            }
+        }
+
+        if weight_sum <= 1e-8 {
+            return center_color;
+        }
+        sum.map(|channel| channel / weight_sum)
+    }
+}
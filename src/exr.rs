@@ -0,0 +1,139 @@
+use std::fs;
+use std::io;
+
+// Escritor mínimo de OpenEXR: una sola parte, scanlines sin compresión.
+// Las capas van como canales con prefijo ("depth.Z", "normal.X", ...), que es
+// como Nuke, Blender y compañía leen los EXR multicapa.
+
+pub enum ExrData {
+    Float(Vec<f32>),
+    // Para identificadores (objeto, material) que no deben interpolarse
+    Uint(Vec<u32>),
+}
+
+pub struct ExrChannel {
+    pub name: String,
+    pub data: ExrData,
+}
+
+pub struct ExrImage {
+    pub width: usize,
+    pub height: usize,
+    pub channels: Vec<ExrChannel>,
+}
+
+impl ExrImage {
+    pub fn new(width: usize, height: usize) -> Self {
+        ExrImage {
+            width,
+            height,
+            channels: Vec::new(),
+        }
+    }
+
+    pub fn add_float(&mut self, name: &str, data: Vec<f32>) {
+        assert_eq!(data.len(), self.width * self.height, "el canal {} no tiene el tamaño de la imagen", name);
+        self.channels.push(ExrChannel { name: name.to_string(), data: ExrData::Float(data) });
+    }
+
+    pub fn add_uint(&mut self, name: &str, data: Vec<u32>) {
+        assert_eq!(data.len(), self.width * self.height, "el canal {} no tiene el tamaño de la imagen", name);
+        self.channels.push(ExrChannel { name: name.to_string(), data: ExrData::Uint(data) });
+    }
+
+    // Tres canales R, G, B; sin capa quedan como el beauty de la imagen
+    pub fn add_rgb(&mut self, layer: Option<&str>, pixels: &[[f32; 3]]) {
+        for (index, channel) in ["R", "G", "B"].iter().enumerate() {
+            let name = match layer {
+                Some(layer) => format!("{}.{}", layer, channel),
+                None => channel.to_string(),
+            };
+            self.add_float(&name, pixels.iter().map(|pixel| pixel[index]).collect());
+        }
+    }
+
+    // Tres canales X, Y, Z (normales, posiciones)
+    pub fn add_vector(&mut self, layer: &str, pixels: &[[f32; 3]]) {
+        for (index, channel) in ["X", "Y", "Z"].iter().enumerate() {
+            self.add_float(&format!("{}.{}", layer, channel), pixels.iter().map(|pixel| pixel[index]).collect());
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.encode())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        // El formato exige los canales en orden alfabético
+        let mut channels: Vec<&ExrChannel> = self.channels.iter().collect();
+        channels.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut out = Vec::new();
+        out.extend_from_slice(&[0x76, 0x2f, 0x31, 0x01]);
+        // Versión 2; nombres largos si alguno pasa de 31 bytes
+        let long_names = channels.iter().any(|channel| channel.name.len() > 31);
+        out.extend_from_slice(&(2u32 | if long_names { 0x400 } else { 0 }).to_le_bytes());
+
+        let mut chlist = Vec::new();
+        for channel in &channels {
+            chlist.extend_from_slice(channel.name.as_bytes());
+            chlist.push(0);
+            let pixel_type: i32 = match channel.data {
+                ExrData::Uint(_) => 0,
+                ExrData::Float(_) => 2,
+            };
+            chlist.extend_from_slice(&pixel_type.to_le_bytes());
+            // pLinear y tres bytes reservados
+            chlist.extend_from_slice(&[0, 0, 0, 0]);
+            chlist.extend_from_slice(&1i32.to_le_bytes());
+            chlist.extend_from_slice(&1i32.to_le_bytes());
+        }
+        chlist.push(0);
+
+        let window = [0, 0, self.width as i32 - 1, self.height as i32 - 1];
+        let window: Vec<u8> = window.iter().flat_map(|value| value.to_le_bytes()).collect();
+
+        attribute(&mut out, "channels", "chlist", &chlist);
+        attribute(&mut out, "compression", "compression", &[0]);
+        attribute(&mut out, "dataWindow", "box2i", &window);
+        attribute(&mut out, "displayWindow", "box2i", &window);
+        attribute(&mut out, "lineOrder", "lineOrder", &[0]);
+        attribute(&mut out, "pixelAspectRatio", "float", &1.0f32.to_le_bytes());
+        attribute(&mut out, "screenWindowCenter", "v2f", &[0u8; 8]);
+        attribute(&mut out, "screenWindowWidth", "float", &1.0f32.to_le_bytes());
+        out.push(0);
+
+        // Tabla de offsets: un bloque por scanline, cada uno con y, tamaño y los
+        // valores de la fila canal por canal
+        let line_size: usize = channels.len() * self.width * 4;
+        let table_start = out.len();
+        let first_block = table_start + self.height * 8;
+        for y in 0..self.height {
+            let offset = (first_block + y * (8 + line_size)) as u64;
+            out.extend_from_slice(&offset.to_le_bytes());
+        }
+
+        for y in 0..self.height {
+            out.extend_from_slice(&(y as i32).to_le_bytes());
+            out.extend_from_slice(&(line_size as i32).to_le_bytes());
+            let row = y * self.width..(y + 1) * self.width;
+            for channel in &channels {
+                match &channel.data {
+                    ExrData::Float(data) => data[row.clone()].iter().for_each(|value| out.extend_from_slice(&value.to_le_bytes())),
+                    ExrData::Uint(data) => data[row.clone()].iter().for_each(|value| out.extend_from_slice(&value.to_le_bytes())),
+                }
+            }
+        }
+
+        out
+    }
+}
+
+fn attribute(out: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    out.extend_from_slice(name.as_bytes());
+    out.push(0);
+    out.extend_from_slice(kind.as_bytes());
+    out.push(0);
+    out.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    out.extend_from_slice(value);
+}
@@ -84,4 +84,59 @@ impl Framebuffer {
     pub fn get_buffer(&self) -> &[u32] {
         &self.buffer
     }
-}
\ No newline at end of file
+
+    // Guarda la imagen; el formato sale de la extensión (png, jpg, ...)
+    pub fn save(&self, path: &str) -> image::ImageResult<()> {
+        let mut image = image::RgbImage::new(self.width as u32, self.height as u32);
+        for (pixel, &color) in image.pixels_mut().zip(&self.buffer) {
+            *pixel = image::Rgb([(color >> 16) as u8, (color >> 8) as u8, color as u8]);
+        }
+        image.save(path)
+    }
+}
+
+// Framebuffer de punto flotante sin recortar (1.0 = blanco), para exportar a EXR y PFM
+pub struct HdrFramebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub buffer: Vec<[f32; 3]>,
+}
+
+impl HdrFramebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        HdrFramebuffer {
+            width,
+            height,
+            buffer: vec![[0.0; 3]; width * height],
+        }
+    }
+
+    // Los colores del raytracer están en espacio sRGB; los formatos HDR esperan valores lineales
+    pub fn to_linear(&self) -> Vec<[f32; 3]> {
+        self.buffer.iter().map(|pixel| pixel.map(srgb_to_linear)).collect()
+    }
+
+    pub fn save_pfm(&self, path: &str) -> std::io::Result<()> {
+        write_pfm(path, self.width, self.height, &self.to_linear())
+    }
+}
+
+// PFM a color ("PF"): filas de abajo hacia arriba, little-endian
+pub fn write_pfm(path: &str, width: usize, height: usize, pixels: &[[f32; 3]]) -> std::io::Result<()> {
+    let mut contents = format!("PF\n{} {}\n-1.0\n", width, height).into_bytes();
+    for row in pixels.chunks(width).rev() {
+        for value in row.iter().flatten() {
+            contents.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    std::fs::write(path, contents)
+}
+
+// Curva sRGB extendida para valores mayores que 1
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::Duration;
+use crate::raytracer::RenderSettings;
+
+const MIN_SCALE: f32 = 0.1;
+const MAX_SCALE: f32 = 1.0;
+// La escala se redondea a este paso para no cambiar el tamaño del framebuffer en cada cuadro
+const SCALE_STEP: f32 = 0.05;
+// Las muestras van de un cuadrado perfecto al siguiente (1, 4, ...): los patrones en
+// rejilla redondean hacia arriba a N×N, así que los valores intermedios cuestan lo mismo
+const MAX_SAMPLES: u32 = 4;
+// Margen alrededor del objetivo dentro del cual no se toca nada (evita oscilar)
+const DEAD_BAND: f32 = 0.15;
+// Peso del último cuadro en el promedio móvil del tiempo de render
+const SMOOTHING: f32 = 0.25;
+
+// Elige la resolución y las muestras de los cuadros en movimiento para acercarse a
+// un tiempo por cuadro objetivo. Primero baja las muestras y después la escala;
+// al sobrar tiempo sube la escala hasta la resolución completa y después las muestras.
+pub struct QualityGovernor {
+    pub target_frame_time: Duration,
+    // Fracción de la resolución de la ventana en cada eje
+    pub scale: f32,
+    pub samples_per_pixel: u32,
+    // Promedio móvil del tiempo de render en segundos
+    pub smoothed_frame_time: f32,
+}
+
+impl QualityGovernor {
+    pub fn new(target_frame_time: Duration) -> Self {
+        QualityGovernor {
+            target_frame_time,
+            scale: 0.5,
+            samples_per_pixel: 1,
+            smoothed_frame_time: target_frame_time.as_secs_f32(),
+        }
+    }
+
+    pub fn render_size(&self, width: usize, height: usize) -> (usize, usize) {
+        (
+            ((width as f32 * self.scale).round() as usize).max(1),
+            ((height as f32 * self.scale).round() as usize).max(1),
+        )
+    }
+
+    pub fn settings(&self) -> RenderSettings {
+        RenderSettings {
+            samples_per_pixel: self.samples_per_pixel,
+            ..RenderSettings::default()
+        }
+    }
+
+    // Registra cuánto tardó el último cuadro en movimiento y ajusta la calidad
+    pub fn record(&mut self, frame_time: Duration) {
+        let frame_time = frame_time.as_secs_f32();
+        self.smoothed_frame_time += (frame_time - self.smoothed_frame_time) * SMOOTHING;
+
+        let target = self.target_frame_time.as_secs_f32();
+        let ratio = target / self.smoothed_frame_time.max(1e-4);
+        if (ratio - 1.0).abs() < DEAD_BAND {
+            return;
+        }
+
+        let side = (self.samples_per_pixel as f32).sqrt().ceil() as u32;
+        if ratio < 1.0 && self.samples_per_pixel > 1 {
+            let samples = (side - 1).pow(2).max(1);
+            self.smoothed_frame_time *= samples as f32 / (side * side) as f32;
+            self.samples_per_pixel = samples;
+            return;
+        }
+        if ratio > 1.0 && self.scale >= MAX_SCALE {
+            // El tiempo crece con las muestras: solo se sube si el cuadro más caro cabe
+            let samples = (side + 1).pow(2);
+            let cost = samples as f32 / (side * side) as f32;
+            if ratio > cost + DEAD_BAND && samples <= MAX_SAMPLES {
+                self.samples_per_pixel = samples;
+                self.smoothed_frame_time *= cost;
+            }
+            return;
+        }
+
+        // El tiempo crece con el número de pixeles, es decir con la escala al cuadrado.
+        // Se avanza solo la mitad del camino hacia la escala ideal.
+        let ideal = self.scale * ratio.sqrt();
+        let scale = self.scale + (ideal - self.scale) * 0.5;
+        let scale = ((scale / SCALE_STEP).round() * SCALE_STEP).clamp(MIN_SCALE, MAX_SCALE);
+        if scale != self.scale {
+            // El promedio se corrige con la predicción para no reaccionar dos veces al mismo error
+            self.smoothed_frame_time *= (scale / self.scale).powi(2);
+            self.scale = scale;
+        }
+    }
+
+    // Resumen para el título de la ventana
+    pub fn status(&self) -> String {
+        format!(
+            "{:.0}% · {} spp · {:.0} ms",
+            self.scale * 100.0,
+            self.samples_per_pixel,
+            self.smoothed_frame_time * 1000.0
+        )
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use crate::block_index::{block_of, BlockIndex};
+use crate::block_model::BlockModel;
+use crate::cross::CrossBlock;
+use crate::cube::Cube;
+use crate::ray_intersect::{Material, RayIntersect};
+
+// Un cambio en la escena que sabe deshacerse. Los objetos van en `Arc`, así guardar uno
+// cuesta un puntero y no una copia del bloque.
+#[derive(Clone)]
+pub enum Edit {
+    Place(Arc<dyn RayIntersect>),
+    Remove(Arc<dyn RayIntersect>),
+    // El mismo bloque con otro material (antes, después)
+    Replace(Arc<dyn RayIntersect>, Arc<dyn RayIntersect>),
+}
+
+impl Edit {
+    pub fn inverse(&self) -> Edit {
+        match self {
+            Edit::Place(object) => Edit::Remove(object.clone()),
+            Edit::Remove(object) => Edit::Place(object.clone()),
+            Edit::Replace(before, after) => Edit::Replace(after.clone(), before.clone()),
+        }
+    }
+
+    // Aplica el cambio. Devuelve false si no se pudo (el lugar está ocupado o el objeto
+    // ya no está) y entonces la escena queda igual.
+    pub fn apply(&self, objects: &mut Vec<Arc<dyn RayIntersect>>, index: &mut BlockIndex) -> bool {
+        match self {
+            Edit::Place(object) => {
+                if block_of(object).is_some_and(|block| index.get(&block).is_some()) {
+                    return false;
+                }
+                index.place(objects, object.clone());
+                true
+            }
+            Edit::Remove(object) => match find(objects, index, object) {
+                Some(position) => {
+                    index.remove(objects, position);
+                    true
+                }
+                None => false,
+            },
+            Edit::Replace(before, after) => match find(objects, index, before) {
+                Some(position) => {
+                    // Mismo bloque: el índice no cambia
+                    objects[position] = after.clone();
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+// Historial de ediciones para deshacer y rehacer. Cada entrada es un comando con todas
+// las ediciones de un clic, un arrastre o un relleno.
+pub struct History {
+    undo: VecDeque<Vec<Edit>>,
+    redo: Vec<Vec<Edit>>,
+    // Comando que se está armando mientras se arrastra el ratón
+    open: Option<Vec<Edit>>,
+    // Ediciones guardadas en `undo`, para acotar la memoria
+    edits: usize,
+    pub max_commands: usize,
+    pub max_edits: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            open: None,
+            edits: 0,
+            max_commands: 256,
+            max_edits: 20_000,
+        }
+    }
+}
+
+impl History {
+    // Lo que se aplique hasta `end` se deshace de una sola vez
+    pub fn begin(&mut self) {
+        self.end();
+        self.open = Some(Vec::new());
+    }
+
+    pub fn end(&mut self) {
+        if let Some(edits) = self.open.take() {
+            self.push(edits);
+        }
+    }
+
+    // Aplica `edit` y lo guarda en el comando abierto, o solo si no hay ninguno
+    pub fn apply(&mut self, edit: Edit, objects: &mut Vec<Arc<dyn RayIntersect>>, index: &mut BlockIndex) -> bool {
+        if !edit.apply(objects, index) {
+            return false;
+        }
+        match &mut self.open {
+            Some(edits) => edits.push(edit),
+            None => self.push(vec![edit]),
+        }
+        true
+    }
+
+    pub fn undo(&mut self, objects: &mut Vec<Arc<dyn RayIntersect>>, index: &mut BlockIndex) -> bool {
+        self.end();
+        let Some(edits) = self.undo.pop_back() else {
+            return false;
+        };
+        self.edits -= edits.len();
+        for edit in edits.iter().rev() {
+            edit.inverse().apply(objects, index);
+        }
+        self.redo.push(edits);
+        true
+    }
+
+    pub fn redo(&mut self, objects: &mut Vec<Arc<dyn RayIntersect>>, index: &mut BlockIndex) -> bool {
+        self.end();
+        let Some(edits) = self.redo.pop() else {
+            return false;
+        };
+        for edit in &edits {
+            edit.apply(objects, index);
+        }
+        self.edits += edits.len();
+        self.undo.push_back(edits);
+        true
+    }
+
+    // Un comando nuevo invalida lo deshecho. Si se pasa de los límites se olvidan los más
+    // viejos, pero el último siempre se conserva.
+    fn push(&mut self, edits: Vec<Edit>) {
+        if edits.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.edits += edits.len();
+        self.undo.push_back(edits);
+        while self.undo.len() > 1 && (self.undo.len() > self.max_commands || self.edits > self.max_edits) {
+            if let Some(oldest) = self.undo.pop_front() {
+                self.edits -= oldest.len();
+            }
+        }
+    }
+}
+
+// Copia de `object` con todas sus caras de `material`; None si no es un bloque que se pueda pintar
+pub fn repainted(object: &Arc<dyn RayIntersect>, material: &Material) -> Option<Arc<dyn RayIntersect>> {
+    let any = object.as_any();
+    if let Some(cube) = any.downcast_ref::<Cube>() {
+        return Some(Arc::new(Cube {
+            materials: std::array::from_fn(|_| material.clone()),
+            ..cube.clone()
+        }));
+    }
+    if let Some(model) = any.downcast_ref::<BlockModel>() {
+        return Some(Arc::new(BlockModel {
+            materials: std::array::from_fn(|_| material.clone()),
+            ..model.clone()
+        }));
+    }
+    if let Some(planta) = any.downcast_ref::<CrossBlock>() {
+        let mut planta = planta.clone();
+        planta.material = material.clone();
+        return Some(Arc::new(planta));
+    }
+    None
+}
+
+// Posición actual de `object` en la escena. La animación del agua puede cambiar el `Arc`
+// por una copia, así que si no está el mismo se toma el que ocupa su bloque.
+fn find(objects: &[Arc<dyn RayIntersect>], index: &BlockIndex, object: &Arc<dyn RayIntersect>) -> Option<usize> {
+    let block = block_of(object);
+    let indexed = block.and_then(|block| index.get(&block));
+    if let Some(position) = indexed.filter(|&position| Arc::ptr_eq(&objects[position], object)) {
+        return Some(position);
+    }
+    objects.iter().position(|candidate| Arc::ptr_eq(candidate, object)).or(indexed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Vector3;
+
+    fn material(id: u32) -> Material {
+        let mut material = Material::black();
+        material.id = id;
+        material
+    }
+
+    fn cube(x: i32, material_id: u32) -> Arc<dyn RayIntersect> {
+        Arc::new(Cube::new(Vector3::new(x as f32, 0.0, 0.0), 1.0, std::array::from_fn(|_| material(material_id))))
+    }
+
+    // Un bloque en x = 0 con el material 1
+    fn scene() -> (Vec<Arc<dyn RayIntersect>>, BlockIndex) {
+        let objects = vec![cube(0, 1)];
+        let index = BlockIndex::new(&objects);
+        (objects, index)
+    }
+
+    // Bloques ocupados, ordenados por x; también comprueba que el índice esté al día
+    fn blocks(objects: &[Arc<dyn RayIntersect>], index: &BlockIndex) -> Vec<i32> {
+        let mut blocks: Vec<i32> = objects.iter().map(|object| block_of(object).unwrap().x).collect();
+        for (position, object) in objects.iter().enumerate() {
+            assert_eq!(index.get(&block_of(object).unwrap()), Some(position));
+        }
+        blocks.sort_unstable();
+        blocks
+    }
+
+    fn material_at(objects: &[Arc<dyn RayIntersect>], index: &BlockIndex, x: i32) -> u32 {
+        let position = index.get(&Vector3::new(x, 0, 0)).unwrap();
+        objects[position].as_any().downcast_ref::<Cube>().unwrap().materials[0].id
+    }
+
+    #[test]
+    fn undo_and_redo_place_remove_and_replace() {
+        let (mut objects, mut index) = scene();
+        let mut history = History::default();
+
+        assert!(history.apply(Edit::Place(cube(1, 2)), &mut objects, &mut index));
+        assert!(!history.apply(Edit::Place(cube(1, 2)), &mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [0, 1]);
+        assert!(history.undo(&mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [0]);
+        assert!(history.redo(&mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [0, 1]);
+
+        let first = objects[index.get(&Vector3::zeros()).unwrap()].clone();
+        assert!(history.apply(Edit::Remove(first), &mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [1]);
+        assert!(history.undo(&mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [0, 1]);
+        assert!(history.redo(&mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [1]);
+
+        let before = objects[index.get(&Vector3::new(1, 0, 0)).unwrap()].clone();
+        let after = repainted(&before, &material(3)).unwrap();
+        assert!(history.apply(Edit::Replace(before, after), &mut objects, &mut index));
+        assert_eq!(material_at(&objects, &index, 1), 3);
+        assert!(history.undo(&mut objects, &mut index));
+        assert_eq!(material_at(&objects, &index, 1), 2);
+        assert!(history.redo(&mut objects, &mut index));
+        assert_eq!(material_at(&objects, &index, 1), 3);
+    }
+
+    #[test]
+    fn drag_is_undone_as_one_command() {
+        let (mut objects, mut index) = scene();
+        let mut history = History::default();
+
+        history.begin();
+        for x in 1..4 {
+            history.apply(Edit::Place(cube(x, 2)), &mut objects, &mut index);
+        }
+        history.end();
+        assert_eq!(blocks(&objects, &index), [0, 1, 2, 3]);
+
+        assert!(history.undo(&mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [0]);
+        assert!(!history.undo(&mut objects, &mut index));
+        assert!(history.redo(&mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn new_command_clears_redo() {
+        let (mut objects, mut index) = scene();
+        let mut history = History::default();
+
+        history.apply(Edit::Place(cube(1, 2)), &mut objects, &mut index);
+        history.undo(&mut objects, &mut index);
+        history.apply(Edit::Place(cube(2, 2)), &mut objects, &mut index);
+        assert!(!history.redo(&mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [0, 2]);
+    }
+
+    #[test]
+    fn oldest_commands_are_evicted() {
+        let (mut objects, mut index) = scene();
+        let mut history = History {
+            max_commands: 2,
+            ..History::default()
+        };
+        for x in 1..4 {
+            history.apply(Edit::Place(cube(x, 2)), &mut objects, &mut index);
+        }
+        assert!(history.undo(&mut objects, &mut index));
+        assert!(history.undo(&mut objects, &mut index));
+        assert!(!history.undo(&mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [0, 1]);
+
+        let (mut objects, mut index) = scene();
+        let mut history = History {
+            max_edits: 3,
+            ..History::default()
+        };
+        for xs in [1..3, 3..5, 5..10] {
+            history.begin();
+            for x in xs {
+                history.apply(Edit::Place(cube(x, 2)), &mut objects, &mut index);
+            }
+            history.end();
+        }
+        // El último se conserva aunque por sí solo pase el límite
+        assert!(history.undo(&mut objects, &mut index));
+        assert!(!history.undo(&mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn undo_finds_objects_copied_by_the_animation() {
+        let (mut objects, mut index) = scene();
+        let mut history = History::default();
+        history.apply(Edit::Place(cube(1, 2)), &mut objects, &mut index);
+
+        // Como `make_mut` con el `Arc` compartido: el mismo bloque en otro `Arc`
+        let position = index.get(&Vector3::new(1, 0, 0)).unwrap();
+        let copy = objects[position].as_any().downcast_ref::<Cube>().unwrap().clone();
+        objects[position] = Arc::new(copy);
+
+        assert!(history.undo(&mut objects, &mut index));
+        assert_eq!(blocks(&objects, &index), [0]);
+    }
+}
@@ -0,0 +1,78 @@
+use nalgebra::{Matrix3, Matrix4, Point3, Vector3};
+use crate::ray_intersect::{Intersect, RayIntersect};
+use std::any::Any;
+use crate::aabb::Aabb;
+use std::sync::Arc;
+
+// Envuelve cualquier primitiva con una transformación 4x4. La geometría se comparte
+// con Arc, así que miles de copias solo cuestan una matriz cada una.
+pub struct Instance {
+    pub object: Arc<dyn RayIntersect>,
+    transform: Matrix4<f32>,
+    inverse: Matrix4<f32>,
+    normal_matrix: Matrix3<f32>,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn RayIntersect>, transform: Matrix4<f32>) -> Self {
+        let inverse = transform.try_inverse().expect("la transformación de la instancia debe ser invertible");
+        let normal_matrix = inverse.fixed_view::<3, 3>(0, 0).transpose();
+
+        Instance {
+            object,
+            transform,
+            inverse,
+            normal_matrix,
+        }
+    }
+
+    fn to_object_point(&self, point: &Vector3<f32>) -> Vector3<f32> {
+        self.inverse.transform_point(&Point3::from(*point)).coords
+    }
+
+    fn to_object_vector(&self, vector: &Vector3<f32>) -> Vector3<f32> {
+        self.inverse.transform_vector(vector)
+    }
+}
+
+impl RayIntersect for Instance {
+    fn ray_intersect(&self, ray_origin: &Vector3<f32>, ray_direction: &Vector3<f32>) -> Intersect {
+        // La dirección no se normaliza: así el parámetro t del espacio objeto
+        // es el mismo que en el espacio mundo.
+        let local_origin = self.to_object_point(ray_origin);
+        let local_direction = self.to_object_vector(ray_direction);
+
+        let mut intersect = self.object.ray_intersect(&local_origin, &local_direction);
+        if !intersect.is_intersecting {
+            return intersect;
+        }
+
+        intersect.point = ray_origin + ray_direction * intersect.distance;
+        intersect.normal = (self.normal_matrix * intersect.normal).normalize();
+        intersect
+    }
+
+    fn get_uv(&self, point: &Vector3<f32>) -> (f32, f32) {
+        self.object.get_uv(&self.to_object_point(point))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn collision_boxes(&self) -> Vec<Aabb> {
+        self.object
+            .collision_boxes()
+            .iter()
+            .map(|aabb| aabb.transformed(&self.transform))
+            .collect()
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.object.bounds().map(|aabb| aabb.transformed(&self.transform))
+    }
+}
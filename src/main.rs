@@ -6,870 +6,602 @@ mod light;
 mod color;
 mod texture;
 mod cube;
+mod instance;
+mod block_model;
+mod cross;
+mod stereo;
+mod controls;
+mod aabb;
+mod physics;
+mod camera_path;
+mod scene;
+mod cli;
+mod exr;
+mod aov;
+mod sampling;
+mod accumulation;
+mod denoise;
+mod governor;
+mod temporal;
+mod upscale;
+mod tiles;
+mod render_thread;
+mod picking;
+mod block_index;
+mod font;
+mod overlay;
+mod history;
 
 use framebuffer::Framebuffer;
-use nalgebra::Vector3;
-use ray_intersect::Material;
-use camera::Camera;
-use light::Light;
-use color::Color;
+use camera::{ApertureShape, Camera, FisheyeMapping, Projection, Stereo, StereoLayout};
 use std::f32::consts::PI;
 use std::time::Duration;
 use std::io::{self, Write};
-use minifb::{Key, Window, WindowOptions};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, ScaleMode, Window, WindowOptions};
+use nalgebra::Vector3;
+use std::sync::Arc;
+use std::thread::JoinHandle;
 use std::path::Path;
 use std::time::Instant;
-use crate::raytracer::render;
-use crate::texture::Texture;
-use crate::ray_intersect::RayIntersect;
+use crate::governor::QualityGovernor;
+use crate::render_thread::{RenderThread, Snapshot};
+use crate::tiles::TileScheduler;
+use crate::ray_intersect::{make_mut, Material, RayIntersect};
+use crate::history::{repainted, Edit, History};
+use crate::block_index::BlockIndex;
+use crate::picking::{pick, Pick};
+use crate::raytracer::autofocus_distance;
+use crate::overlay::{block_info, draw_outline, draw_panel, outline_pixels};
+use crate::physics::EYE_HEIGHT;
+use crate::aov::AovView;
 use crate::cube::Cube;
+use crate::cross::CrossBlock;
+use crate::controls::{CameraController, ControlMode};
+use crate::camera_path::{CameraPath, Interpolation};
+use crate::scene::Scene;
 
+const CAMERA_PATH_FILE: &str = "camera_path.txt";
+const FRAMES_DIR: &str = "frames";
+// Cuánto se corre el agua al animarse; menos de medio bloque, así no pasa al vecino
+const WATER_AMPLITUDE: f32 = 0.1;
+// Bloques que puede tener como mucho un relleno con G
+const MAX_FILL_VOLUME: usize = 4096;
+// Teclas que eligen el material de la paleta, en el orden de la escena
+const PALETTE_KEYS: [Key; 9] = [Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9];
 
 fn main() {
-    let width = 800;
-    let height = 600;
+    let options = match cli::Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    if options.headless {
+        std::process::exit(cli::run_headless(&options));
+    }
+
+    let scene = options.load_scene().unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    });
+
+    // Tamaño del render: el de la ventana por la escala
+    let (mut width, mut height) = render_size((options.width, options.height), options.scale);
 
+    // Lo que se ve en la ventana; los cuadros llegan del hilo de render
     let mut framebuffer_high = Framebuffer::new(width, height);
-    let mut framebuffer_medium = Framebuffer::new(width / 2, height / 2);
-    let mut framebuffer_low = Framebuffer::new(width / 6, height / 6); // Ultra low for movement
-    let mut framebuffer_ultra_low = Framebuffer::new(width / 8, height / 8); // Emergency performance mode
+    let governor = QualityGovernor::new(Duration::from_secs_f32(options.target_frame_ms / 1000.0));
+    let tile_scheduler = TileScheduler {
+        order: options.tile_order,
+        ..TileScheduler::default()
+    };
+    let render_thread = RenderThread::spawn(governor, tile_scheduler);
+    let mut upscaler = options.upscaler;
 
-    let mut window = Window::new(
-        "Minecraft Diorama Raytracing",
-        width,
-        height,
-        WindowOptions::default(),
-    )
-    .unwrap_or_else(|e| {
-        panic!("{}", e);
-    });
+    let mut fullscreen = options.fullscreen;
+    let mut window = open_window(options.width, options.height, fullscreen, options.fps);
+    // Tamaño y posición para volver de pantalla completa
+    let mut windowed = ((options.width, options.height), (0, 0));
+
+    let Scene { mut objects, lights, mut camera, materials } = scene;
+    let agua_texture = materials
+        .iter()
+        .find(|(name, _)| name == "agua")
+        .and_then(|(_, material)| material.texture.clone());
 
-    let agua_texture = Texture::load_from_file("assets/agua.jpg");
-    let tierra_texture = Texture::load_from_file("assets/tierra.jpeg");
-    let tierra_grama_texture = Texture::load_from_file("assets/tierra2.png");
-    let grama_texture = Texture::load_from_file("assets/grama.png");
-    let madera_texture = Texture::load_from_file("assets/madera.jpg");
-    let hoja_texture = Texture::load_from_file("assets/hoja_arbol.jpg");
-    let piedra_texture = Texture::load_from_file("assets/piedra.png");
-    let arena_texture = Texture::load_from_file("assets/arena.png");
-
-    //let textura_solida = Color::new(255, 0, 0);
-    //let material_prueba = Material::new(textura_solida, 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, false, None);
-
-    let mut camera = Camera::new(
-        Vector3::new(0.0, 5.0, -10.0),  
-        Vector3::new(0.0, 0.0, 0.0), 
-        Vector3::new(0.0, 1.0, 0.0),  
-    );
-
-    // Definimos la luz
-    let lights = vec![
-        Light::new(Vector3::new(100.0, 100.0, -100.0), Color::new(255, 255, 255), 2.0, 5.0), 
-        Light::new(Vector3::new(-100.0, -100.0, 100.0), Color::new(255, 255, 255), 2.0, 5.0),
-    ];
-
-    // Definimos los materiales 
-    let tierra_grama = Material::new(Color::new(255, 255, 255), 32.0, [0.9, 0.1, 0.0, 0.0], 1.0, true, Some(tierra_grama_texture.clone()));
-    let tierra = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(tierra_texture.clone()));
-    let grama = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(grama_texture.clone()));
-    let agua = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(agua_texture.clone()));
-    let madera = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(madera_texture.clone()));
-    let piedra = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(piedra_texture.clone()));
-    let hoja = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(hoja_texture.clone()));
-    let arena = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(arena_texture.clone()));
-
-
-    let mut objects: Vec<Box<dyn RayIntersect>> = Vec::new();
-
-    // Base de 8x8 cubos
-    let grid_size = 8;
-    let cube_size = 1.0;
-    
-    for z in 0..grid_size {
-        for x in 0..grid_size {
-            let x_pos = x as f32 * cube_size - (grid_size as f32 / 2.0) * cube_size;
-            let z_pos = z as f32 * cube_size - (grid_size as f32 / 2.0) * cube_size;
-            
-            objects.push(Box::new(Cube {
-                center: Vector3::new(x_pos, 0.0, z_pos),  
-                size: cube_size,                        
-                materials: [
-                    tierra.clone(),  // Derecha
-                    tierra.clone(),  // Izquierda
-                    tierra.clone(),  // Abajo
-                    tierra.clone(),  // Arriba
-                    tierra.clone(),  // Frente
-                    tierra.clone(),  // Atrás
-                ],
-            }));
-        }
-    }
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 1.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 1.0, 2.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 1.0, 1.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 1.0, 1.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 1.0, 0.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 1.0, 0.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 1.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(0.0, 1.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(0.0, 1.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-1.0, 1.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-1.0, 1.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-2.0, 1.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-2.0, 1.0, -4.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-3.0, 1.0, -4.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 1.0, -4.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 1.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 1.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 1.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-3.0, 1.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-3.0, 1.0, 0.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-2.0, 1.0, 0.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-2.0, 1.0, 1.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-1.0, 1.0, 1.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-1.0, 1.0, 2.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(0.0, 1.0, 2.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(0.0, 1.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 1.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 1.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 1.0, 2.0),  
-        size: 1.0,                          
-        materials: [
-            agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 1.0, 2.0),  
-        size: 1.0,                          
-        materials: [
-            agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 1.0, 1.0),  
-        size: 1.0,                          
-        materials: [
-            agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(0.0, 1.0, 1.0),  
-        size: 1.0,                          
-        materials: [
-            agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(0.0, 1.0, 0.0),  
-        size: 1.0,                          
-        materials: [
-            agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-1.0, 1.0, 0.0),  
-        size: 1.0,                          
-        materials: [
-            agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-1.0, 1.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-2.0, 1.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-2.0, 1.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-3.0, 1.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-3.0, 1.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 1.0, 0.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 1.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 1.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 1.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 1.0, -4.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 1.0, -4.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 1.0, -4.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(0.0, 1.0, -4.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-1.0, 1.0, -4.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 1.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 1.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 1.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 1.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(0.0, 1.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 2.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 2.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 2.0, -4.0),  
-        size: 1.0,                          
-        materials: [
-            tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 2.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 2.0, -4.0),  
-        size: 1.0,                          
-        materials: [
-            tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 2.0, -4.0),  
-        size: 1.0,                          
-        materials: [
-            tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-1.0, 1.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-2.0, 1.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-3.0, 1.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 1.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 1.0, 2.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 1.0, 1.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 1.0, 0.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-3.0, 1.0, 1.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-2.0, 1.0, 2.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-2.0, 2.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-3.0, 2.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-3.0, 2.0, 2.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 2.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 2.0, 2.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 2.0, 1.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-3.0, 3.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 3.0, 3.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(-4.0, 3.0, 2.0),  
-        size: 1.0,                          
-        materials: [
-            piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 2.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 3.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 6.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 5.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 5.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 5.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 5.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 4.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(2.0, 4.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 4.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 4.0, -2.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 4.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 4.0, -1.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(1.0, 4.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-    objects.push(Box::new(Cube {
-        center: Vector3::new(3.0, 4.0, -3.0),  
-        size: 1.0,                          
-        materials: [
-            hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
-        ],
-    }));
-
-    let glass = Material::new(Color::new(255, 255, 255), 125.0, [0.0, 0.5, 0.1, 0.8], 1.5, false, None); // Vidrio, 80% transparente, índice de refracción 1.5
-    
-    let mut needs_render = true;
-    let mut camera_moved = false;
     let animation_start = Instant::now();
     let mut last_animation_update = Instant::now();
-    let mut last_frame_time = Instant::now();
-    let mut movement_frames = 0;
-    let mut frame_count = 0;
-    
-    // Render initial frame at medium quality for faster startup
-    render(&mut framebuffer_medium, &objects, &camera, &lights);
-    let scaled_initial = upscale_framebuffer(framebuffer_medium.get_buffer(), framebuffer_medium.width, framebuffer_medium.height, width, height);
-    window.update_with_buffer(&scaled_initial, width, height).unwrap();
-    
+    let mut title = String::new();
+    let mut vista = 0;
+    let mut aov_view = AovView::Beauty;
+    let mut animate = true;
+    let mut denoise = false;
+    // Reutiliza el cuadro anterior en movimiento en vez de trazar todos los pixeles
+    let mut reproject = true;
+    let mut controller = CameraController::new();
+    let mut last_input_time = Instant::now();
+    let mut camera_path = CameraPath::load(CAMERA_PATH_FILE).unwrap_or_else(|_| CameraPath::new(Interpolation::CatmullRom));
+    let mut last_keyframe_time = Instant::now();
+    let mut playback_start: Option<Instant> = None;
+    // Hilo que renderiza el recorrido con O sin frenar la ventana
+    let mut path_render: Option<JoinHandle<io::Result<usize>>> = None;
+    // El primer cuadro sale a resolución reducida para arrancar rápido
+    let mut camera_moved = true;
+    let mut scene_changed = true;
+    // Qué objeto ocupa cada bloque, para editar sin recorrer la escena
+    let mut block_index = BlockIndex::new(&objects);
+    // Material de los bloques que se colocan; las teclas 1 a 9 eligen de la paleta
+    let mut selected_material = 0;
+    // Botones del ratón en el cuadro anterior, para actuar solo al presionarlos
+    let mut mouse_down = (false, false);
+    // Ediciones para deshacer, y dónde quedó el puntero para saber si un arrastre avanzó
+    let mut history = History::default();
+    let mut last_pointer: Option<(f32, f32)> = None;
+    let mut last_edited: Option<Vector3<i32>> = None;
+    // Primera esquina del relleno con G
+    let mut fill_corner: Option<Vector3<i32>> = None;
+    let mut status = String::new();
+    // F3 muestra u oculta el panel con los datos del bloque apuntado
+    let mut show_info = true;
+    // Contorno del bloque apuntado y a qué objeto y bloque corresponde
+    let mut outline: Vec<usize> = Vec::new();
+    let mut outlined: Option<(usize, Vector3<i32>)> = None;
+    // Cámara del cuadro que se está mostrando
+    let mut frame_camera = camera.clone();
+
     while window.is_open() && !window.is_key_down(Key::Escape) {
-        camera_moved = false;
+        let dt = last_input_time.elapsed().as_secs_f32();
+        last_input_time = Instant::now();
 
-        if window.is_key_down(Key::W) {
-            camera.mover_enfrente(0.4);
+        // F11 alterna entre la ventana normal y una sin bordes en la esquina de la pantalla
+        if window.is_key_pressed(Key::F11, KeyRepeat::No) {
+            fullscreen = !fullscreen;
+            let ((window_width, window_height), (x, y)) = if fullscreen {
+                windowed = (window.get_size(), window.get_position());
+                (windowed.0, (0, 0))
+            } else {
+                windowed
+            };
+            window = open_window(window_width, window_height, fullscreen, options.fps);
+            window.set_position(x, y);
+            window.set_cursor_visibility(controller.mode == ControlMode::Orbit);
+        }
+        // La ventana pudo cambiar de tamaño: se renderiza al nuevo y con su proporción
+        let size = render_size(window.get_size(), options.scale);
+        if size != (width, height) {
+            (width, height) = size;
             camera_moved = true;
         }
-        if window.is_key_down(Key::S) {
-            camera.mover_atras(0.4);
+
+        if window.is_key_pressed(Key::C, KeyRepeat::No) {
+            controller.next_mode(&mut window);
+        }
+        // Recorridos de cámara: K agrega un cuadro clave, J reproduce, L guarda y O renderiza los cuadros
+        if window.is_key_pressed(Key::K, KeyRepeat::No) {
+            let time = camera_path.keyframes.last().map_or(0.0, |last| {
+                last.time + last_keyframe_time.elapsed().as_secs_f32().min(10.0)
+            });
+            camera_path.add_keyframe(&camera, time);
+            last_keyframe_time = Instant::now();
+            println!("Cuadro clave {} en {:.2} s", camera_path.keyframes.len(), time);
+        }
+        if window.is_key_pressed(Key::Backspace, KeyRepeat::No) {
+            // Los cuadros se agregan en orden de tiempo, así que el último es el más nuevo
+            camera_path.keyframes.pop();
+            last_keyframe_time = Instant::now();
+            playback_start = None;
+        }
+        if window.is_key_pressed(Key::J, KeyRepeat::No) {
+            playback_start = match playback_start {
+                None if camera_path.keyframes.len() >= 2 => Some(Instant::now()),
+                _ => None,
+            };
+        }
+        if window.is_key_pressed(Key::L, KeyRepeat::No) {
+            match camera_path.save(CAMERA_PATH_FILE) {
+                Ok(()) => println!("Recorrido guardado en {}", CAMERA_PATH_FILE),
+                Err(e) => eprintln!("No se pudo guardar el recorrido: {}", e),
+            }
+        }
+        if window.is_key_pressed(Key::O, KeyRepeat::No) && camera_path.keyframes.len() >= 2 {
+            if path_render.is_some() {
+                println!("El recorrido ya se está renderizando");
+            } else {
+                println!("Renderizando recorrido en {}/ ...", FRAMES_DIR);
+                // Con una copia de la escena: lo que se edite mientras tanto no entra en los cuadros
+                let (path, path_camera, path_objects, path_lights) = (camera_path.clone(), camera.clone(), objects.clone(), lights.clone());
+                let settings = options.render_settings();
+                let (path_width, path_height) = (width, height);
+                path_render = Some(std::thread::spawn(move || {
+                    path.render_frames(&path_camera, 30.0, path_width, path_height, &path_objects, &path_lights, &settings, FRAMES_DIR)
+                }));
+            }
+        }
+        if let Some(handle) = path_render.take_if(|handle| handle.is_finished()) {
+            match handle.join() {
+                Ok(Ok(frames)) => println!("{} cuadros renderizados", frames),
+                Ok(Err(e)) => eprintln!("No se pudo renderizar el recorrido: {}", e),
+                Err(_) => eprintln!("No se pudo renderizar el recorrido"),
+            }
+        }
+
+        if let Some(start) = playback_start {
+            let time = start.elapsed().as_secs_f32();
+            camera_path.apply(time, &mut camera);
+            if time >= camera_path.duration() {
+                playback_start = None;
+            }
+            camera_moved = true;
+        } else {
+            camera_moved |= controller.update(&window, &mut camera, &objects, dt);
+        }
+
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            vista = (vista + 1) % 8;
+            let distancia = (camera.eye - camera.center).magnitude();
+            match vista {
+                0 => camera.projection = Projection::Perspective { fov_y: PI / 2.0 },
+                1 => camera.projection = Projection::Orthographic { view_height: distancia },
+                2 | 3 => {
+                    let axonometrica = if vista == 2 {
+                        Camera::isometric(camera.center, distancia.max(20.0), 12.0)
+                    } else {
+                        Camera::dimetric(camera.center, distancia.max(20.0), 12.0)
+                    };
+                    // Solo cambian la orientación y la proyección; apertura, inclinación,
+                    // autoenfoque y estéreo se conservan
+                    camera.eye = axonometrica.eye;
+                    camera.center = axonometrica.center;
+                    camera.up = axonometrica.up;
+                    camera.projection = axonometrica.projection;
+                }
+                4 => camera.projection = Projection::Equirectangular,
+                5 => camera.projection = Projection::Fisheye { fov: PI, mapping: FisheyeMapping::Equidistant },
+                6 => camera.projection = Projection::Fisheye { fov: PI * 1.5, mapping: FisheyeMapping::Stereographic },
+                _ => camera.projection = Projection::Cylindrical { fov_x: PI * 1.5 },
+            }
+            camera_moved = true;
+        }
+
+        // F1 recorre las pasadas de depuración: profundidad, normales, albedo, UV e ids
+        if window.is_key_pressed(Key::F1, KeyRepeat::No) {
+            aov_view = aov_view.next();
             camera_moved = true;
         }
-        if window.is_key_down(Key::A) {
-            camera.mover_izq(0.4);
+
+        // R alterna la reproyección temporal de los cuadros en movimiento
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            reproject = !reproject;
+            scene_changed = true;
             camera_moved = true;
         }
-        if window.is_key_down(Key::D) {
-            camera.mover_der(0.4);
+
+        // U cambia el filtro con que se agrandan los cuadros en movimiento
+        if window.is_key_pressed(Key::U, KeyRepeat::No) {
+            upscaler = upscaler.next();
+            camera_moved = true;
+        }
+
+        // M pausa el agua y las plantas para que la imagen quieta pueda converger
+        if window.is_key_pressed(Key::M, KeyRepeat::No) {
+            animate = !animate;
+        }
+
+        // N activa el denoiser guiado por profundidad, normales y albedo
+        if window.is_key_pressed(Key::N, KeyRepeat::No) {
+            denoise = !denoise;
             camera_moved = true;
         }
 
-        if window.is_key_down(Key::Up) {
-            camera.orbit(0.0, -0.1);
+        // Profundidad de campo con autoenfoque
+        if window.is_key_pressed(Key::F, KeyRepeat::No) {
+            if camera.aperture > 0.0 {
+                camera.aperture = 0.0;
+                camera.tilt = 0.0;
+                camera.autofocus = false;
+            } else {
+                camera.aperture = 0.15;
+                camera.autofocus = true;
+            }
             camera_moved = true;
         }
-        if window.is_key_down(Key::Down) {
-            camera.orbit(0.0, 0.1);
+        // Forma del diafragma, que se ve en el desenfoque (bokeh): círculo, cuadrado, hexágono
+        if window.is_key_pressed(Key::B, KeyRepeat::No) {
+            camera.aperture_shape = match camera.aperture_shape {
+                ApertureShape::Circle => ApertureShape::Square,
+                ApertureShape::Square => ApertureShape::Polygon(6),
+                ApertureShape::Polygon(_) => ApertureShape::Circle,
+            };
+            if camera.aperture == 0.0 {
+                camera.aperture = 0.15;
+                camera.autofocus = true;
+            }
             camera_moved = true;
         }
-        if window.is_key_down(Key::Left) {
-            camera.orbit(-0.1, 0.0);
+        // Tilt-shift: plano focal inclinado para el efecto de miniatura
+        if window.is_key_pressed(Key::T, KeyRepeat::No) {
+            if camera.tilt != 0.0 {
+                camera.tilt = 0.0;
+            } else {
+                camera.tilt = 0.3;
+                camera.aperture = camera.aperture.max(0.4);
+                camera.autofocus = true;
+            }
             camera_moved = true;
         }
-        if window.is_key_down(Key::Right) {
-            camera.orbit(0.1, 0.0);
+        // Estéreo: anaglifo, lado a lado y arriba/abajo
+        if window.is_key_pressed(Key::V, KeyRepeat::No) {
+            let layout = match camera.stereo.map(|stereo| stereo.layout) {
+                None => Some(StereoLayout::Anaglyph),
+                Some(StereoLayout::Anaglyph) => Some(StereoLayout::SideBySide),
+                Some(StereoLayout::SideBySide) => Some(StereoLayout::OverUnder),
+                Some(StereoLayout::OverUnder) => None,
+            };
+            // Distancia entre ojos exagerada (los bloques miden 1 m) para que la maqueta tenga relieve
+            camera.stereo = layout.map(|layout| Stereo {
+                interpupillary_distance: 0.065 * 4.0,
+                convergence: (camera.center - camera.eye).magnitude(),
+                layout,
+            });
             camera_moved = true;
         }
 
-        if camera_moved {
-            movement_frames += 1;
-        } else {
-            movement_frames = 0;
-        }
-        
-        frame_count += 1;
-        let frame_time = last_frame_time.elapsed();
-        let target_fps = Duration::from_millis(33); // Target ~30 FPS
-        
-        let should_animate = !camera_moved && last_animation_update.elapsed() >= Duration::from_millis(200);
-        if should_animate {
-            let elapsed_time = animation_start.elapsed().as_secs_f32();
-            for (i, object) in objects.iter_mut().enumerate() {
-                if let Some(cube) = object.as_any_mut().downcast_mut::<Cube>() {
-                    if cube.materials.iter().any(|m| m.has_texture && m.texture.as_ref().map_or(false, |t| t == &agua_texture)) {
-                        let desfase = i as f32 * 0.3;
-                        cube.center.x += (elapsed_time * 0.3 + desfase).sin() * 0.03;
+        if window.is_key_pressed(Key::F3, KeyRepeat::No) {
+            show_info = !show_info;
+        }
+
+        for (number, key) in PALETTE_KEYS.iter().enumerate().take(materials.len()) {
+            if window.is_key_pressed(*key, KeyRepeat::No) {
+                selected_material = number;
+            }
+        }
+        // Ctrl+Z deshace y Ctrl+Y (o Ctrl+Shift+Z) rehace
+        let ctrl = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
+        let shift = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+        if ctrl && window.is_key_pressed(Key::Z, KeyRepeat::Yes) {
+            scene_changed |= if shift { history.redo(&mut objects, &mut block_index) } else { history.undo(&mut objects, &mut block_index) };
+        }
+        if ctrl && window.is_key_pressed(Key::Y, KeyRepeat::Yes) {
+            scene_changed |= history.redo(&mut objects, &mut block_index);
+        }
+
+        // Clic izquierdo rompe el bloque apuntado, el derecho apoya uno nuevo en la cara y
+        // con Alt pinta el apuntado (Shift y Ctrl ya mueven la cámara al volar o caminar).
+        // Arrastrando se siguen editando los bloques por los que pasa el puntero; todo lo de
+        // un mismo arrastre se deshace junto.
+        let alt = window.is_key_down(Key::LeftAlt) || window.is_key_down(Key::RightAlt);
+        let (left, right) = (window.get_mouse_down(MouseButton::Left), window.get_mouse_down(MouseButton::Right));
+        let pointer = window.get_mouse_pos(MouseMode::Pass);
+        if (left || right) && mouse_down == (false, false) {
+            history.begin();
+            last_edited = None;
+        }
+        let pointer_moved = pointer != last_pointer || camera_moved;
+        if (left || right) && (mouse_down == (false, false) || pointer_moved) {
+            if let Some(hit) = pick_under_cursor(&window, &controller, &objects, &camera, width, height) {
+                let material = materials.get(selected_material).map(|(_, material)| material);
+                let (target, edit) = if left {
+                    (hit.block, Some(Edit::Remove(objects[hit.index].clone())))
+                } else if alt {
+                    (hit.block, material.and_then(|material| repainted(&objects[hit.index], material)).map(|after| Edit::Replace(objects[hit.index].clone(), after)))
+                } else {
+                    let block = hit.adjacent();
+                    (block, material.filter(|_| !occupied_by_camera(&camera, &block)).map(|material| Edit::Place(block_cube(block, material))))
+                };
+                if last_edited != Some(target) {
+                    if let Some(edit) = edit {
+                        scene_changed |= history.apply(edit, &mut objects, &mut block_index);
                     }
+                    last_edited = Some(target);
                 }
             }
-            last_animation_update = Instant::now();
         }
+        if !left && !right && mouse_down != (false, false) {
+            history.end();
+        }
+        mouse_down = (left, right);
+        last_pointer = pointer;
 
-        if camera_moved || should_animate {
-            if camera_moved {
-                if frame_time > Duration::from_millis(50) || movement_frames > 10 {
-                    render(&mut framebuffer_ultra_low, &objects, &camera, &lights);
-                    let scaled_framebuffer = upscale_framebuffer(framebuffer_ultra_low.get_buffer(), framebuffer_ultra_low.width, framebuffer_ultra_low.height, width, height);
-                    window.update_with_buffer(&scaled_framebuffer, width, height).unwrap();
-                } else if frame_time > Duration::from_millis(25) {
-                    render(&mut framebuffer_low, &objects, &camera, &lights);
-                    let scaled_framebuffer = upscale_framebuffer(framebuffer_low.get_buffer(), framebuffer_low.width, framebuffer_low.height, width, height);
-                    window.update_with_buffer(&scaled_framebuffer, width, height).unwrap();
-                } else {
-                    render(&mut framebuffer_medium, &objects, &camera, &lights);
-                    let scaled_framebuffer = upscale_framebuffer(framebuffer_medium.get_buffer(), framebuffer_medium.width, framebuffer_medium.height, width, height);
-                    window.update_with_buffer(&scaled_framebuffer, width, height).unwrap();
+        // G marca una esquina del relleno y la segunda vez llena de bloques los lugares
+        // vacíos de la caja entre las dos, como un solo comando
+        if window.is_key_pressed(Key::G, KeyRepeat::No) {
+            let hit = pick_under_cursor(&window, &controller, &objects, &camera, width, height);
+            match (fill_corner.take(), hit, materials.get(selected_material)) {
+                (None, Some(hit), _) => {
+                    fill_corner = Some(hit.adjacent());
+                    println!("Relleno desde {}, {}, {}", hit.adjacent().x, hit.adjacent().y, hit.adjacent().z);
                 }
-            } else {
-                if frame_count % 3 == 0 {
-                    render(&mut framebuffer_high, &objects, &camera, &lights);
-                    window.update_with_buffer(framebuffer_high.get_buffer(), width, height).unwrap();
+                (Some(start), Some(hit), Some((_, material))) => {
+                    let end = hit.adjacent();
+                    let (min, max) = (start.inf(&end), start.sup(&end));
+                    let volume = (max - min).map(|side| side as usize + 1).product();
+                    if volume > MAX_FILL_VOLUME {
+                        println!("El relleno tiene {} bloques, el máximo es {}", volume, MAX_FILL_VOLUME);
+                    } else {
+                        history.begin();
+                        let mut placed = 0;
+                        for x in min.x..=max.x {
+                            for y in min.y..=max.y {
+                                for z in min.z..=max.z {
+                                    let block = Vector3::new(x, y, z);
+                                    if !occupied_by_camera(&camera, &block) && history.apply(Edit::Place(block_cube(block, material)), &mut objects, &mut block_index) {
+                                        placed += 1;
+                                    }
+                                }
+                            }
+                        }
+                        history.end();
+                        println!("{} bloques colocados", placed);
+                        scene_changed |= placed > 0;
+                    }
                 }
+                _ => {}
             }
         }
-        
-        last_frame_time = Instant::now();
 
-        if !camera_moved {
-            std::thread::sleep(Duration::from_millis(100)); 
-        } else {
-            std::thread::sleep(Duration::from_millis(8)); 
+        if camera_moved && camera.autofocus {
+            if let Some(distance) = autofocus_distance(&objects, &camera) {
+                camera.focus_distance = distance;
+            }
         }
-    }
-}
 
-fn upscale_framebuffer(low_res_buffer: &[u32], low_width: usize, low_height: usize, high_width: usize, high_height: usize) -> Vec<u32> {
-    let mut high_res_buffer = vec![0; high_width * high_height];
+        let should_animate = animate && !camera_moved && last_animation_update.elapsed() >= Duration::from_millis(200);
+        let mut animated = false;
+        if should_animate {
+            let elapsed_time = animation_start.elapsed().as_secs_f32();
+            for object in objects.iter_mut() {
+                let es_agua = object
+                    .as_any()
+                    .downcast_ref::<Cube>()
+                    .is_some_and(|cube| cube.materials.iter().any(|m| m.has_texture && m.texture.is_some() && m.texture == agua_texture));
+                if es_agua {
+                    // Oscila alrededor de su lugar, con la fase según la posición para que haga olas
+                    if let Some(cube) = make_mut::<Cube>(object) {
+                        let desfase = (cube.center.x + cube.center.z) * 0.3;
+                        cube.offset.x = (elapsed_time * 0.3 + desfase).sin() * WATER_AMPLITUDE;
+                    }
+                }
+                if object.as_any().is::<CrossBlock>() {
+                    if let Some(planta) = make_mut::<CrossBlock>(object) {
+                        planta.time = elapsed_time;
+                    }
+                }
+            }
+            last_animation_update = Instant::now();
+            animated = true;
+        }
+
+        // El contorno se proyecta con la cámara del cuadro que se muestra, que puede ir
+        // detrás de la actual, así no se despega de la imagen al moverse
+        let mut frame_changed = false;
+        if let Some(frame) = render_thread.latest_frame() {
+            frame_changed = frame.camera != frame_camera || (frame.width, frame.height) != (framebuffer_high.width, framebuffer_high.height);
+            framebuffer_high = Framebuffer {
+                width: frame.width,
+                height: frame.height,
+                buffer: frame.buffer,
+            };
+            frame_camera = frame.camera;
+            status = frame.status;
+        }
+
+        // El contorno depende del cuadro, la escena y el bloque: solo se recalcula si cambió alguno
+        let hover = pick_under_cursor(&window, &controller, &objects, &camera, width, height);
+        let hovered = hover.as_ref().map(|hit| (hit.index, hit.block));
+        if frame_changed || scene_changed || animated || hovered != outlined {
+            outline = match hover.as_ref().and_then(|hit| objects[hit.index].bounds()) {
+                Some(bounds) => outline_pixels(&objects, &frame_camera, &bounds, framebuffer_high.width, framebuffer_high.height),
+                None => Vec::new(),
+            };
+            outlined = hovered;
+        }
+
+        // Solo se manda la escena cuando algo cambió; el hilo de render sigue acumulando la última
+        if camera_moved || scene_changed || animated {
+            render_thread.submit(Snapshot {
+                objects: objects.clone(),
+                lights: lights.clone(),
+                camera: camera.clone(),
+                width,
+                height,
+                view: aov_view,
+                denoise,
+                reproject,
+                upscaler,
+                moving: camera_moved,
+                scene_changed: scene_changed || animated,
+                animation_only: !camera_moved && !scene_changed,
+            });
+            camera_moved = false;
+            scene_changed = false;
+        }
 
-    for y in 0..high_height {
-        let src_y = y * low_height / high_height;
-        for x in 0..high_width {
-            let src_x = x * low_width / high_width;
-            let src_index = src_y * low_width + src_x;
-            let dst_index = y * high_width + x;
-            high_res_buffer[dst_index] = low_res_buffer[src_index];
+        let block_name = materials.get(selected_material).map_or("-", |(name, _)| name.as_str());
+        let new_title = format!("Minecraft Diorama Raytracing - {} - bloque: {}", status, block_name);
+        if new_title != title {
+            window.set_title(&new_title);
+            title = new_title;
         }
+        // El contorno y el panel se dibujan sobre una copia: el cuadro del render sigue limpio
+        // para los próximos
+        let panel = hover.as_ref().filter(|_| show_info).map(|hit| block_info(objects[hit.index].as_ref(), hit, &materials));
+        let mut overlaid = None;
+        if !outline.is_empty() || panel.is_some() {
+            let mut display = Framebuffer {
+                width: framebuffer_high.width,
+                height: framebuffer_high.height,
+                buffer: framebuffer_high.buffer.clone(),
+            };
+            draw_outline(&mut display, &outline);
+            if let Some(lines) = &panel {
+                draw_panel(&mut display, lines);
+            }
+            overlaid = Some(display);
+        }
+        let display = overlaid.as_ref().unwrap_or(&framebuffer_high);
+        // También procesa la entrada y espera lo necesario para mantener los fps. Si el
+        // cuadro es de otro tamaño que la ventana, minifb lo estira.
+        window.update_with_buffer(display.get_buffer(), display.width, display.height).unwrap();
     }
+}
+
+// Tampoco se colocan bloques donde está la cámara o los pies del jugador
+fn occupied_by_camera(camera: &Camera, block: &Vector3<i32>) -> bool {
+    let eye = camera.eye.map(|coordinate| coordinate.round() as i32);
+    let feet = (camera.eye - Vector3::new(0.0, EYE_HEIGHT, 0.0)).map(|coordinate| coordinate.round() as i32);
+    *block == eye || *block == feet
+}
+
+// Bloque entero de un material, como los que coloca el jugador
+fn block_cube(block: Vector3<i32>, material: &Material) -> Arc<dyn RayIntersect> {
+    Arc::new(Cube::new(block.cast::<f32>(), 1.0, std::array::from_fn(|_| material.clone())))
+}
 
-    high_res_buffer
-}
\ No newline at end of file
+// En órbita se apunta con el cursor; volando o caminando el ratón gira la vista, así que
+// se apunta al centro de la imagen como en Minecraft
+fn pick_under_cursor(window: &Window, controller: &CameraController, objects: &[Arc<dyn RayIntersect>], camera: &Camera, width: usize, height: usize) -> Option<Pick> {
+    let (x, y) = if controller.mode == ControlMode::Orbit {
+        let (mouse_x, mouse_y) = window.get_mouse_pos(MouseMode::Discard)?;
+        let (window_width, window_height) = window.get_size();
+        (mouse_x * width as f32 / window_width as f32, mouse_y * height as f32 / window_height as f32)
+    } else {
+        (width as f32 / 2.0, height as f32 / 2.0)
+    };
+    pick(objects, camera, x, y, width, height)
+}
+
+// minifb no sabe el tamaño del monitor: sin bordes la ventana conserva su tamaño, así que
+// para pantalla completa conviene pasar la resolución del monitor en --width y --height
+fn open_window(width: usize, height: usize, borderless: bool, fps: usize) -> Window {
+    let mut window = Window::new(
+        "Minecraft Diorama Raytracing",
+        width,
+        height,
+        WindowOptions {
+            borderless,
+            title: !borderless,
+            resize: true,
+            scale_mode: ScaleMode::Stretch,
+            ..WindowOptions::default()
+        },
+    )
+    .unwrap_or_else(|e| {
+        panic!("{}", e);
+    });
+    // El bucle de la ventana va a este ritmo, renderice lo que renderice el otro hilo
+    window.set_target_fps(fps);
+    window
+}
+
+// Pixeles a renderizar para una ventana de `size`
+fn render_size((width, height): (usize, usize), scale: f32) -> (usize, usize) {
+    (
+        ((width as f32 * scale).round() as usize).max(1),
+        ((height as f32 * scale).round() as usize).max(1),
+    )
+}
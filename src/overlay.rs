@@ -0,0 +1,191 @@
+use nalgebra::Vector3;
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::block_model::{BlockModel, BlockShape};
+use crate::camera::Camera;
+use crate::cross::CrossBlock;
+use crate::cube::Cube;
+use crate::font::{draw_text, text_width, GLYPH_HEIGHT};
+use crate::framebuffer::Framebuffer;
+use crate::instance::Instance;
+use crate::picking::Pick;
+use crate::ray_intersect::{Material, RayIntersect};
+use crate::raytracer::trace;
+
+// Las líneas se dibujan apenas por fuera del bloque para no hundirse en sus caras
+const OUTLINE_MARGIN: f32 = 0.004;
+// Cuánto puede quedar el punto de la arista detrás de lo que trazó el rayo sin taparse
+const OCCLUSION_TOLERANCE: f32 = 0.05;
+// Rayos de oclusión por contorno; se traza en el hilo de la ventana y no puede frenarla
+const MAX_OCCLUSION_RAYS: usize = 512;
+
+// Pixeles de una imagen de `width`×`height` por los que pasan las aristas de `bounds`.
+// Cada punto se compara con lo que encuentra el rayo de su pixel, así las aristas tapadas
+// por otros bloques (o por el mismo, las de atrás) no se dibujan. Solo en proyecciones
+// planas y sin estéreo, que son las que se pueden proyectar.
+pub fn outline_pixels(objects: &[Arc<dyn RayIntersect>], camera: &Camera, bounds: &Aabb, width: usize, height: usize) -> Vec<usize> {
+    if camera.stereo.is_some() {
+        return Vec::new();
+    }
+    let aspect_ratio = width as f32 / height as f32;
+    let margin = Vector3::repeat(OUTLINE_MARGIN);
+    let (min, max) = (bounds.min - margin, bounds.max + margin);
+    let corner = |index: usize| {
+        Vector3::new(
+            if index & 1 == 0 { min.x } else { max.x },
+            if index & 2 == 0 { min.y } else { max.y },
+            if index & 4 == 0 { min.z } else { max.z },
+        )
+    };
+    let to_pixel = |point: &Vector3<f32>| {
+        let (screen_x, screen_y, _) = camera.project(point, aspect_ratio)?;
+        Some(((screen_x + 1.0) / 2.0 * width as f32, (1.0 - screen_y) / 2.0 * height as f32))
+    };
+
+    // Puntos de cada arista cada medio pixel, con el pixel en que caen
+    let mut edges: Vec<Vec<(usize, Vector3<f32>)>> = Vec::new();
+    for start_corner in 0..8 {
+        for axis in 0..3 {
+            if start_corner & (1 << axis) != 0 {
+                continue;
+            }
+            let (start, end) = (corner(start_corner), corner(start_corner | (1 << axis)));
+            let steps = match (to_pixel(&start), to_pixel(&end)) {
+                (Some(a), Some(b)) => ((a.0 - b.0).hypot(a.1 - b.1) * 2.0).ceil() as usize,
+                // Una punta queda detrás de la cámara: se recorre fino
+                _ => 1024,
+            }
+            .clamp(1, 4096);
+            let edge = (0..=steps)
+                .filter_map(|step| {
+                    let point = start.lerp(&end, step as f32 / steps as f32);
+                    let (x, y) = to_pixel(&point)?;
+                    (x >= 0.0 && y >= 0.0 && x < width as f32 && y < height as f32).then(|| (y as usize * width + x as usize, point))
+                })
+                .collect();
+            edges.push(edge);
+        }
+    }
+
+    // Con el bloque grande en pantalla no se traza cada punto: los de en medio toman lo que
+    // dio el último rayo de su arista, así el costo no pasa de MAX_OCCLUSION_RAYS
+    let total: usize = edges.iter().map(Vec::len).sum();
+    let stride = total.div_ceil(MAX_OCCLUSION_RAYS).max(1);
+    let visible = |index: usize, point: &Vector3<f32>| {
+        let screen_x = (2.0 * ((index % width) as f32 + 0.5)) / width as f32 - 1.0;
+        let screen_y = -((2.0 * ((index / width) as f32 + 0.5)) / height as f32 - 1.0);
+        let Some((ray_origin, ray_direction)) = camera.primary_ray(screen_x, screen_y, aspect_ratio) else {
+            return false;
+        };
+        let distance = (point - ray_origin).dot(&ray_direction) / ray_direction.magnitude_squared();
+        let intersect = trace(&ray_origin, &ray_direction, objects);
+        !intersect.is_intersecting || intersect.distance >= distance - OCCLUSION_TOLERANCE
+    };
+
+    let mut pixels = Vec::new();
+    for edge in &edges {
+        let mut edge_visible = false;
+        for (step, (index, point)) in edge.iter().enumerate() {
+            if step % stride == 0 {
+                edge_visible = visible(*index, point);
+            }
+            if edge_visible {
+                pixels.push(*index);
+            }
+        }
+    }
+    pixels.sort_unstable();
+    pixels.dedup();
+    pixels
+}
+
+// Oscurece los pixeles del contorno, como las líneas negras semitransparentes de Minecraft
+pub fn draw_outline(framebuffer: &mut Framebuffer, pixels: &[usize]) {
+    for &index in pixels {
+        if let Some(pixel) = framebuffer.buffer.get_mut(index) {
+            *pixel = darken(*pixel, 0.35);
+        }
+    }
+}
+
+// Líneas del panel de información del bloque apuntado
+pub fn block_info(object: &dyn RayIntersect, pick: &Pick, materials: &[(String, Material)]) -> Vec<String> {
+    let material = &pick.intersect.material;
+    let material_name = materials
+        .iter()
+        .find(|(_, named)| material.id != 0 && named.id == material.id)
+        .map_or("sin nombre", |(name, _)| name.as_str());
+    let yes_no = |value: bool| if value { "si" } else { "no" };
+    let [red, green, blue, alpha] = material.albedo;
+
+    vec![
+        format!("{} en {}, {}, {}", block_type(object), pick.block.x, pick.block.y, pick.block.z),
+        format!("cara: {}", face_name(&pick.face)),
+        format!("material: {} (id {})", material_name, material.id),
+        format!("color: {}, {}, {}  brillo: {}", material.diffuse.r, material.diffuse.g, material.diffuse.b, material.specular),
+        format!("albedo: {:.2} {:.2} {:.2} {:.2}", red, green, blue, alpha),
+        format!("refraccion: {:.2}  textura: {}  solido: {}", material.refractive_index, yes_no(material.has_texture), yes_no(material.solid)),
+        format!("distancia: {:.1}", pick.intersect.distance),
+    ]
+}
+
+// Recuadro oscuro arriba a la izquierda con una línea de texto por renglón
+pub fn draw_panel(framebuffer: &mut Framebuffer, lines: &[String]) {
+    // La letra crece con la imagen para que se siga leyendo en ventanas grandes
+    let scale = (framebuffer.width / 640).max(1);
+    let padding = 4 * scale;
+    let line_height = (GLYPH_HEIGHT + 3) * scale;
+    let panel_width = lines.iter().map(|line| text_width(line, scale)).max().unwrap_or(0) + 2 * padding;
+    let panel_height = lines.len() * line_height + 2 * padding - 3 * scale;
+
+    for y in padding..(padding + panel_height).min(framebuffer.height) {
+        for x in padding..(padding + panel_width).min(framebuffer.width) {
+            let pixel = &mut framebuffer.buffer[y * framebuffer.width + x];
+            *pixel = darken(*pixel, 0.35);
+        }
+    }
+    for (row, line) in lines.iter().enumerate() {
+        draw_text(framebuffer, 2 * padding, 2 * padding + row * line_height, line, 0xFFFFFF, scale);
+    }
+}
+
+fn block_type(object: &dyn RayIntersect) -> String {
+    let any = object.as_any();
+    if any.is::<Cube>() {
+        "bloque".to_string()
+    } else if any.is::<CrossBlock>() {
+        "planta".to_string()
+    } else if let Some(model) = any.downcast_ref::<BlockModel>() {
+        match model.shape {
+            BlockShape::Slab(_) => "losa".to_string(),
+            BlockShape::Stairs(..) => "escalera".to_string(),
+            BlockShape::Fence(_) => "cerca".to_string(),
+            BlockShape::Wall(_) => "muro".to_string(),
+            BlockShape::Pane(_) => "panel".to_string(),
+            BlockShape::Carpet => "alfombra".to_string(),
+            BlockShape::Fluid(level) => format!("fluido nivel {}", level),
+        }
+    } else if let Some(instance) = any.downcast_ref::<Instance>() {
+        format!("{} transformado", block_type(instance.object.as_ref()))
+    } else {
+        "objeto".to_string()
+    }
+}
+
+// Los puntos cardinales siguen a los de las escaleras: norte es -Z y este es +X
+fn face_name(face: &Vector3<i32>) -> &'static str {
+    match (face.x, face.y, face.z) {
+        (0, 1, 0) => "arriba (+y)",
+        (0, -1, 0) => "abajo (-y)",
+        (0, 0, -1) => "norte (-z)",
+        (0, 0, 1) => "sur (+z)",
+        (1, 0, 0) => "este (+x)",
+        (-1, 0, 0) => "oeste (-x)",
+        _ => "-",
+    }
+}
+
+fn darken(color: u32, factor: f32) -> u32 {
+    let [r, g, b] = [16, 8, 0].map(|shift| (((color >> shift) & 0xFF) as f32 * factor) as u32);
+    (r << 16) | (g << 8) | b
+}
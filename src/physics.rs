@@ -0,0 +1,148 @@
+use nalgebra::Vector3;
+use crate::aabb::Aabb;
+use crate::ray_intersect::RayIntersect;
+use std::sync::Arc;
+
+// Medidas y constantes del jugador de Minecraft, en bloques y segundos
+const PLAYER_WIDTH: f32 = 0.6;
+const PLAYER_HEIGHT: f32 = 1.8;
+pub const EYE_HEIGHT: f32 = 1.62;
+const GRAVITY: f32 = 32.0;
+const JUMP_SPEED: f32 = 9.0;
+const TERMINAL_SPEED: f32 = 60.0;
+// Lo justo para subir un bloque completo sin saltar
+const STEP_HEIGHT: f32 = 1.05;
+const EPSILON: f32 = 1e-4;
+// Por debajo de esta altura el jugador cayó fuera del diorama
+const VOID_HEIGHT: f32 = -30.0;
+
+pub fn colliders(objects: &[Arc<dyn RayIntersect>]) -> Vec<Aabb> {
+    objects.iter().flat_map(|object| object.collision_boxes()).collect()
+}
+
+// Altura del bloque más alto en la columna (x, z) que ocuparía el jugador
+pub fn ground_height(x: f32, z: f32, colliders: &[Aabb]) -> Option<f32> {
+    let column = Aabb::new(
+        Vector3::new(x - PLAYER_WIDTH / 2.0, f32::NEG_INFINITY, z - PLAYER_WIDTH / 2.0),
+        Vector3::new(x + PLAYER_WIDTH / 2.0, f32::INFINITY, z + PLAYER_WIDTH / 2.0),
+    );
+    colliders
+        .iter()
+        .filter(|collider| collider.intersects(&column))
+        .map(|collider| collider.max.y)
+        .reduce(f32::max)
+}
+
+pub struct Player {
+    // Posición de los pies (centro de la base de la caja)
+    pub position: Vector3<f32>,
+    pub velocity: Vector3<f32>,
+    pub on_ground: bool,
+    spawn: Vector3<f32>,
+}
+
+impl Player {
+    pub fn new(position: Vector3<f32>) -> Self {
+        Player {
+            position,
+            velocity: Vector3::zeros(),
+            on_ground: false,
+            spawn: position,
+        }
+    }
+
+    pub fn eye(&self) -> Vector3<f32> {
+        self.position + Vector3::new(0.0, EYE_HEIGHT, 0.0)
+    }
+
+    fn bounds_at(&self, position: Vector3<f32>) -> Aabb {
+        let mitad = PLAYER_WIDTH / 2.0;
+        Aabb::new(
+            position - Vector3::new(mitad, 0.0, mitad),
+            position + Vector3::new(mitad, PLAYER_HEIGHT, mitad),
+        )
+    }
+
+    // `wish` es la velocidad horizontal deseada (bloques por segundo)
+    pub fn update(&mut self, wish: Vector3<f32>, jump: bool, dt: f32, colliders: &[Aabb]) {
+        // Pasos cortos para que una caída rápida no atraviese bloques delgados
+        let dt = dt.min(0.1);
+
+        if jump && self.on_ground {
+            self.velocity.y = JUMP_SPEED;
+        }
+        self.velocity.y = (self.velocity.y - GRAVITY * dt).max(-TERMINAL_SPEED);
+        self.velocity.x = wish.x;
+        self.velocity.z = wish.z;
+
+        let delta = self.velocity * dt;
+        let was_on_ground = self.on_ground;
+
+        let moved = self.sweep(self.position, delta, colliders);
+        let mut position = moved;
+
+        // Si una pared frenó el movimiento horizontal, probar subiendo el escalón
+        let blocked = (moved.x - self.position.x - delta.x).abs() > EPSILON
+            || (moved.z - self.position.z - delta.z).abs() > EPSILON;
+        if blocked && was_on_ground {
+            let raised = self.sweep(self.position, Vector3::new(0.0, STEP_HEIGHT, 0.0), colliders);
+            let across = self.sweep(raised, Vector3::new(delta.x, 0.0, delta.z), colliders);
+            let down = self.sweep(across, Vector3::new(0.0, -(across.y - self.position.y), 0.0), colliders);
+
+            let horizontal = |p: Vector3<f32>| (p.x - self.position.x).hypot(p.z - self.position.z);
+            if horizontal(down) > horizontal(moved) + EPSILON {
+                position = down;
+            }
+        }
+
+        let landed = delta.y < 0.0 && position.y > self.position.y + delta.y + EPSILON;
+        let hit_ceiling = delta.y > 0.0 && position.y < self.position.y + delta.y - EPSILON;
+        self.on_ground = landed || (was_on_ground && self.resting_on_ground(position, colliders));
+        if landed || hit_ceiling {
+            self.velocity.y = 0.0;
+        }
+
+        self.position = position;
+
+        if self.position.y < VOID_HEIGHT {
+            self.position = self.spawn;
+            self.velocity = Vector3::zeros();
+        }
+    }
+
+    fn resting_on_ground(&self, position: Vector3<f32>, colliders: &[Aabb]) -> bool {
+        let probe = self.bounds_at(position).translated(Vector3::new(0.0, -2.0 * EPSILON, 0.0));
+        colliders.iter().any(|collider| collider.intersects(&probe))
+    }
+
+    // Barrido por ejes, como Minecraft: primero Y, luego X y Z. Cada eje recorta el
+    // desplazamiento contra las cajas que se solapan con el jugador en los otros dos ejes.
+    fn sweep(&self, start: Vector3<f32>, delta: Vector3<f32>, colliders: &[Aabb]) -> Vector3<f32> {
+        let mut position = start;
+        for axis in [1, 0, 2] {
+            if delta[axis] == 0.0 {
+                continue;
+            }
+            let bounds = self.bounds_at(position);
+            let mut allowed = delta[axis];
+
+            for collider in colliders {
+                let overlaps_others = (0..3)
+                    .filter(|&other| other != axis)
+                    .all(|other| bounds.min[other] < collider.max[other] && bounds.max[other] > collider.min[other]);
+                if !overlaps_others {
+                    continue;
+                }
+
+                if allowed > 0.0 && bounds.max[axis] <= collider.min[axis] + EPSILON {
+                    allowed = allowed.min(collider.min[axis] - bounds.max[axis] - EPSILON).max(0.0);
+                } else if allowed < 0.0 && bounds.min[axis] >= collider.max[axis] - EPSILON {
+                    allowed = allowed.max(collider.max[axis] - bounds.min[axis] + EPSILON).min(0.0);
+                }
+            }
+
+            position[axis] += allowed;
+        }
+        position
+    }
+}
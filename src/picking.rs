@@ -0,0 +1,52 @@
+use nalgebra::Vector3;
+use std::sync::Arc;
+use crate::aabb::Aabb;
+use crate::camera::Camera;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::raytracer::trace_with_index;
+
+// Lo que se ve en un pixel de la imagen
+pub struct Pick {
+    // Posición del objeto en la lista de la escena
+    pub index: usize,
+    pub intersect: Intersect,
+    // Normal de la cara que chocó, llevada al eje más cercano
+    pub face: Vector3<i32>,
+    // Bloque de la grilla que ocupa el objeto
+    pub block: Vector3<i32>,
+}
+
+impl Pick {
+    // Bloque del otro lado de la cara, donde se apoya uno nuevo
+    pub fn adjacent(&self) -> Vector3<i32> {
+        self.block + self.face
+    }
+}
+
+// Traza el rayo que pasa por (`x`, `y`) en una imagen de `width`×`height` pixeles con la
+// misma cuenta que `render`, así lo que se elige es lo que se ve
+pub fn pick(objects: &[Arc<dyn RayIntersect>], camera: &Camera, x: f32, y: f32, width: usize, height: usize) -> Option<Pick> {
+    let screen_x = (2.0 * x) / width as f32 - 1.0;
+    let screen_y = -((2.0 * y) / height as f32 - 1.0);
+    let (ray_origin, ray_direction) = camera.primary_ray(screen_x, screen_y, width as f32 / height as f32)?;
+    let (intersect, index) = trace_with_index(&ray_origin, &ray_direction, objects);
+    let index = index?;
+
+    let normal = intersect.normal;
+    let axis = normal.iamax();
+    let mut face = Vector3::zeros();
+    face[axis] = if normal[axis] < 0.0 { -1 } else { 1 };
+
+    // Los objetos que no son bloques se ubican por el punto donde chocó el rayo
+    let block = match objects[index].bounds() {
+        Some(bounds) => block_position(&bounds),
+        None => (intersect.point - normal * 0.01).map(|coordinate| coordinate.round() as i32),
+    };
+
+    Some(Pick { index, intersect, face, block })
+}
+
+// Los bloques miden 1 y están centrados en coordenadas enteras
+pub fn block_position(bounds: &Aabb) -> Vector3<i32> {
+    ((bounds.min + bounds.max) / 2.0).map(|coordinate| coordinate.round() as i32)
+}
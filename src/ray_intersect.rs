@@ -2,6 +2,8 @@ use nalgebra::Vector3;
 use crate::color::Color;
 use crate::texture::Texture;
 use std::any::Any;
+use std::sync::Arc;
+use crate::aabb::Aabb;
 
 #[derive(Debug, Clone)]
 pub struct Material {
@@ -11,6 +13,10 @@ pub struct Material {
     pub refractive_index: f32,
     pub has_texture: bool,    
     pub texture: Option<Texture>,
+    // Los materiales no sólidos (agua) no bloquean al jugador
+    pub solid: bool,
+    // Índice en la paleta de la escena, para la pasada de material (0 = sin nombre)
+    pub id: u32,
 }
 
 impl Material {
@@ -29,9 +35,16 @@ impl Material {
             refractive_index,
             has_texture,
             texture,
+            solid: true,
+            id: 0,
         }
     }
 
+    pub fn non_solid(mut self) -> Self {
+        self.solid = false;
+        self
+    }
+
     pub fn black() -> Self {
         Material {
             diffuse: Color::new(0, 0, 0),
@@ -40,6 +53,8 @@ impl Material {
             refractive_index: 1.0,
             has_texture: false,  
             texture: None,   
+            solid: true,
+            id: 0,
         }
     }
 
@@ -50,6 +65,14 @@ impl Material {
             self.diffuse
         }
     }
+
+    // Prueba alfa: los pixeles casi transparentes de la textura no se dibujan
+    pub fn is_transparent_at(&self, u: f32, v: f32) -> bool {
+        match &self.texture {
+            Some(texture) if self.has_texture => texture.get_alpha(u, v) < 128,
+            _ => false,
+        }
+    }
 }
 
 pub struct Intersect {
@@ -91,7 +114,28 @@ impl Intersect {
 pub trait RayIntersect: Any + Send + Sync {
     fn ray_intersect(&self, ray_origin: &Vector3<f32>, ray_direction: &Vector3<f32>) -> Intersect;
     fn get_uv(&self, point: &Vector3<f32>) -> (f32, f32);
+    fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    // Cajas contra las que choca el jugador en modo caminar
+    fn collision_boxes(&self) -> Vec<Aabb> {
+        Vec::new()
+    }
+
+    // Caja del bloque que ocupa el objeto, para ubicarlo en la grilla; None si no es un bloque
+    fn bounds(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+// Como `Arc::make_mut` para los objetos de la escena: acceso mutable al objeto si es un
+// `T`. Si el hilo de render todavía usa la versión anterior, se cambia por una copia.
+pub fn make_mut<T: RayIntersect + Clone>(object: &mut Arc<dyn RayIntersect>) -> Option<&mut T> {
+    if Arc::get_mut(object).is_none() {
+        let copy = object.as_any().downcast_ref::<T>()?.clone();
+        *object = Arc::new(copy);
+    }
+    Arc::get_mut(object)?.as_any_mut().downcast_mut::<T>()
 }
 
 /*
@@ -1,59 +1,402 @@
 use nalgebra::Vector3;
 use rayon::prelude::*;
-use crate::framebuffer::Framebuffer;
+use rand::rngs::ThreadRng;
+use rand::Rng;
+use crate::framebuffer::{Framebuffer, HdrFramebuffer};
 use crate::ray_intersect::{Intersect, RayIntersect, Material};
-use crate::camera::Camera;
+use crate::camera::{Camera, Projection, StereoLayout};
+use crate::aov::{AovPixel, Aovs};
 use crate::light::Light;
 use crate::color::Color;
+use crate::stereo::{anaglyph_color, eye_viewports, render_stereo, render_stereo_hdr};
+use crate::sampling::{Adaptive, Filter, SamplePattern};
+use crate::tiles::Tile;
+use std::sync::Arc;
 
-pub fn render(framebuffer: &mut Framebuffer, objects: &[Box<dyn RayIntersect>], camera: &Camera, lights: &[Light]) {
-    let width = framebuffer.width;
-    let height = framebuffer.height;
-    let aspect_ratio = width as f32 / height as f32;
+#[derive(Debug, Clone, Copy)]
+pub struct RenderSettings {
+    // Muestras por pixel; las rejillas redondean hacia arriba a N×N
+    pub samples_per_pixel: u32,
+    pub pattern: SamplePattern,
+    pub filter: Filter,
+    pub adaptive: Option<Adaptive>,
+    // Cada muestra ve la luz desde un punto al azar de su esfera (`Light::radius`); al
+    // promediar cuadros las sombras quedan con penumbra
+    pub soft_shadows: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        // Un rayo por el centro de cada pixel
+        RenderSettings {
+            samples_per_pixel: 1,
+            pattern: SamplePattern::Stratified,
+            filter: Filter::Box,
+            adaptive: None,
+            soft_shadows: false,
+        }
+    }
+}
+
+pub fn render_with_settings(framebuffer: &mut Framebuffer, objects: &[Arc<dyn RayIntersect>], camera: &Camera, lights: &[Light], settings: &RenderSettings) {
+    render_with_aovs(framebuffer, objects, camera, lights, settings, None);
+}
+
+// Además del color llena las pasadas auxiliares (profundidad, normales, albedo, UV, ids).
+// En estéreo las pasadas quedan vacías: no hay una sola vista a la que correspondan.
+pub fn render_with_aovs(framebuffer: &mut Framebuffer, objects: &[Arc<dyn RayIntersect>], camera: &Camera, lights: &[Light], settings: &RenderSettings, aovs: Option<&mut Aovs>) {
+    if let Some(stereo) = camera.stereo {
+        render_stereo(framebuffer, objects, camera, lights, &stereo, settings);
+        if let Some(aovs) = aovs {
+            aovs.clear();
+        }
+        return;
+    }
+
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    render_pixels(&mut framebuffer.buffer, aovs, width, height, objects, camera, lights, settings, |color| {
+        let [r, g, b] = [color.x, color.y, color.z].map(|channel| (channel * 255.0).round().clamp(0.0, 255.0) as u32);
+        (r << 16) | (g << 8) | b
+    });
+}
+
+// Igual que `render_with_aovs` pero sin recortar el color a 8 bits
+pub fn render_hdr(framebuffer: &mut HdrFramebuffer, objects: &[Arc<dyn RayIntersect>], camera: &Camera, lights: &[Light], settings: &RenderSettings, aovs: Option<&mut Aovs>) {
+    if let Some(stereo) = camera.stereo {
+        render_stereo_hdr(framebuffer, objects, camera, lights, &stereo, settings);
+        if let Some(aovs) = aovs {
+            aovs.clear();
+        }
+        return;
+    }
+
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    render_pixels(&mut framebuffer.buffer, aovs, width, height, objects, camera, lights, settings, |color| [color.x, color.y, color.z]);
+}
+
+// Recorre la imagen en paralelo y guarda en cada pixel el promedio filtrado de sus
+// muestras (color sin recortar, 1.0 = blanco) convertido con `store`. Las pasadas
+// auxiliares salen del impacto de la muestra más cercana al centro del pixel.
+#[allow(clippy::too_many_arguments)]
+fn render_pixels<T: Send>(
+    buffer: &mut [T],
+    aovs: Option<&mut Aovs>,
+    width: usize,
+    height: usize,
+    objects: &[Arc<dyn RayIntersect>],
+    camera: &Camera,
+    lights: &[Light],
+    settings: &RenderSettings,
+    store: impl Fn(Vector3<f32>) -> T + Sync,
+) {
+    let sampler = PixelSampler {
+        width,
+        height,
+        objects,
+        camera,
+        lights,
+        filter: settings.filter,
+        soft_shadows: settings.soft_shadows,
+    };
     let chunk_size = 32; 
 
-    // Use chunked parallelization which is more efficient for raytracing
-    framebuffer.buffer.par_chunks_mut(width * chunk_size).enumerate().for_each(|(chunk_idx, chunk)| {
+    // Suma ponderada del color y suma de los pesos de cada pixel
+    let mut accumulated: Vec<(Vector3<f32>, f32)> = vec![(Vector3::zeros(), 0.0); width * height];
+
+    let render_chunk = |chunk_idx: usize, chunk: &mut [(Vector3<f32>, f32)], mut aov_chunk: Option<&mut [AovPixel]>| {
         let base_y = chunk_idx * chunk_size;
+        let mut rng = rand::thread_rng();
         
         for (local_y, row) in chunk.chunks_mut(width).enumerate() {
             let y = base_y + local_y;
             if y >= height { break; }
-            
-            let screen_y = -((2.0 * y as f32) / height as f32 - 1.0);
 
             for (x, pixel) in row.iter_mut().enumerate() {
-                let screen_x = (2.0 * x as f32) / width as f32 - 1.0;
-                let screen_x = screen_x * aspect_ratio;
+                let offsets = settings.pattern.offsets(settings.samples_per_pixel, settings.filter, &mut rng);
+                let aov = aov_chunk.as_deref_mut().map(|aov_chunk| &mut aov_chunk[local_y * width + x]);
+                *pixel = sampler.accumulate(x, y, &offsets, &mut rng, aov);
+            }
+        }
+    };
+
+    // Use chunked parallelization which is more efficient for raytracing
+    match aovs {
+        Some(aovs) => accumulated
+            .par_chunks_mut(width * chunk_size)
+            .zip(aovs.pixels.par_chunks_mut(width * chunk_size))
+            .enumerate()
+            .for_each(|(chunk_idx, (chunk, aov_chunk))| render_chunk(chunk_idx, chunk, Some(aov_chunk))),
+        None => accumulated
+            .par_chunks_mut(width * chunk_size)
+            .enumerate()
+            .for_each(|(chunk_idx, chunk)| render_chunk(chunk_idx, chunk, None)),
+    }
+
+    if let Some(adaptive) = settings.adaptive {
+        let refine = high_contrast_pixels(&accumulated, width, height, adaptive.threshold);
+        accumulated.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            let mut rng = rand::thread_rng();
+            for (x, pixel) in row.iter_mut().enumerate() {
+                if refine[y * width + x] {
+                    let offsets = SamplePattern::Jittered.offsets(adaptive.extra_samples, settings.filter, &mut rng);
+                    let (color, weight) = sampler.accumulate(x, y, &offsets, &mut rng, None);
+                    pixel.0 += color;
+                    pixel.1 += weight;
+                }
+            }
+        });
+    }
 
-                let ray_direction = camera.base_change(&Vector3::new(screen_x, screen_y, -1.0).normalize());
+    buffer.par_iter_mut().zip(accumulated.par_iter()).for_each(|(pixel, &(color, weight))| {
+        *pixel = store(resolve(color, weight));
+    });
+}
+
+// Renderiza solo el rectángulo de `tile` de una imagen de `width`×`height`, en el hilo
+// actual: el planificador de tiles ya reparte el trabajo. El paso adaptativo compara
+// únicamente con los vecinos dentro del tile.
+#[allow(clippy::too_many_arguments)]
+pub fn render_tile(
+    tile: &Tile,
+    width: usize,
+    height: usize,
+    objects: &[Arc<dyn RayIntersect>],
+    camera: &Camera,
+    lights: &[Light],
+    settings: &RenderSettings,
+    with_aovs: bool,
+) -> (Vec<Vector3<f32>>, Option<Vec<AovPixel>>) {
+    let sampler = PixelSampler {
+        width,
+        height,
+        objects,
+        camera,
+        lights,
+        filter: settings.filter,
+        soft_shadows: settings.soft_shadows,
+    };
+    // En estéreo cada pixel se traza con la cámara del ojo que lo ve, en la imagen de ese ojo
+    let eyes = camera.stereo.map(|stereo| (stereo.layout, eye_viewports(camera, &stereo, width, height)));
+    let eye_samplers = eyes.as_ref().map(|(layout, viewports)| {
+        let samplers = viewports.each_ref().map(|eye| {
+            let sampler = PixelSampler {
+                width: eye.width,
+                height: eye.height,
+                camera: &eye.camera,
+                ..sampler
+            };
+            (eye, sampler)
+        });
+        (*layout, samplers)
+    });
+    // Las pasadas auxiliares quedan vacías en estéreo, como en `render_with_aovs`
+    let sample = |x: usize, y: usize, offsets: &[(f32, f32)], rng: &mut ThreadRng, aov: Option<&mut AovPixel>| match &eye_samplers {
+        None => sampler.accumulate(x, y, offsets, rng, aov),
+        Some((StereoLayout::Anaglyph, [(_, left), (_, right)])) => {
+            // Las mismas muestras en los dos ojos, así los pesos coinciden
+            let (left_color, weight) = left.accumulate(x, y, offsets, rng, None);
+            let (right_color, _) = right.accumulate(x, y, offsets, rng, None);
+            (anaglyph_color(&left_color, &right_color), weight)
+        }
+        Some((_, [left, right])) => {
+            let (eye, sampler) = if right.0.contains(x, y) { right } else { left };
+            sampler.accumulate(x - eye.x, y - eye.y, offsets, rng, None)
+        }
+    };
+    let mut rng = rand::thread_rng();
+    let pixels = || (tile.y..tile.y + tile.height).flat_map(|y| (tile.x..tile.x + tile.width).map(move |x| (x, y)));
 
-                let mut pixel_color = Color::new(0, 0, 0);
+    let mut aovs = with_aovs.then(|| vec![AovPixel::empty(); tile.width * tile.height]);
+    let mut accumulated: Vec<(Vector3<f32>, f32)> = pixels()
+        .enumerate()
+        .map(|(index, (x, y))| {
+            let offsets = settings.pattern.offsets(settings.samples_per_pixel, settings.filter, &mut rng);
+            let aov = aovs.as_mut().map(|aovs| &mut aovs[index]);
+            sample(x, y, &offsets, &mut rng, aov)
+        })
+        .collect();
 
-                if let Some(light) = lights.first() {
-                    let light_contrib = cast_ray(&camera.eye, &ray_direction, objects, light, 0);
-                    pixel_color = light_contrib;
+    if let Some(adaptive) = settings.adaptive {
+        let refine = high_contrast_pixels(&accumulated, tile.width, tile.height, adaptive.threshold);
+        for ((pixel, (x, y)), refine) in accumulated.iter_mut().zip(pixels()).zip(refine) {
+            if refine {
+                let offsets = SamplePattern::Jittered.offsets(adaptive.extra_samples, settings.filter, &mut rng);
+                let (color, weight) = sample(x, y, &offsets, &mut rng, None);
+                pixel.0 += color;
+                pixel.1 += weight;
+            }
+        }
+    }
+
+    (accumulated.into_iter().map(|(color, weight)| resolve(color, weight)).collect(), aovs)
+}
+
+// Promedio ponderado; Mitchell tiene lóbulos negativos, así que se recorta en 0
+fn resolve(color: Vector3<f32>, weight: f32) -> Vector3<f32> {
+    if weight.abs() < 1e-6 {
+        return Vector3::zeros();
+    }
+    (color / weight).map(|channel| channel.max(0.0))
+}
+
+// Pixeles cuya vecindad 3×3 tiene una diferencia de luminancia mayor que `threshold`
+fn high_contrast_pixels(accumulated: &[(Vector3<f32>, f32)], width: usize, height: usize, threshold: f32) -> Vec<bool> {
+    let luminance: Vec<f32> = accumulated
+        .iter()
+        .map(|&(color, weight)| {
+            let color = resolve(color, weight);
+            0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+        })
+        .collect();
+
+    (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = (index % width, index / width);
+            let (mut min, mut max) = (f32::INFINITY, f32::NEG_INFINITY);
+            for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                    let value = luminance[ny * width + nx];
+                    min = min.min(value);
+                    max = max.max(value);
                 }
+            }
+            max - min > threshold
+        })
+        .collect()
+}
 
-                *pixel = ((pixel_color.r as u32) << 16)
-                    | ((pixel_color.g as u32) << 8)
-                    | (pixel_color.b as u32);
+struct PixelSampler<'a> {
+    width: usize,
+    height: usize,
+    objects: &'a [Arc<dyn RayIntersect>],
+    camera: &'a Camera,
+    lights: &'a [Light],
+    filter: Filter,
+    soft_shadows: bool,
+}
+
+impl PixelSampler<'_> {
+    // Punto de pantalla en [-1, 1] para una posición en pixeles (y hacia abajo)
+    fn screen(&self, pixel_x: f32, pixel_y: f32) -> (f32, f32) {
+        (
+            (2.0 * pixel_x) / self.width as f32 - 1.0,
+            -((2.0 * pixel_y) / self.height as f32 - 1.0),
+        )
+    }
+
+    fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    // Suma ponderada por el filtro de las muestras en `offsets` alrededor del centro del pixel.
+    // Con `aov` guarda ahí las pasadas auxiliares del impacto de la muestra más centrada.
+    fn accumulate(&self, x: usize, y: usize, offsets: &[(f32, f32)], rng: &mut impl Rng, mut aov: Option<&mut AovPixel>) -> (Vector3<f32>, f32) {
+        let mut color = Vector3::zeros();
+        let mut weight_sum = 0.0;
+        let distance = |&(dx, dy): &(f32, f32)| dx * dx + dy * dy;
+        let centered = aov.is_some().then(|| (0..offsets.len()).min_by(|&a, &b| distance(&offsets[a]).total_cmp(&distance(&offsets[b])))).flatten();
+
+        for (sample, &(dx, dy)) in offsets.iter().enumerate() {
+            let weight = self.filter.weight(dx, dy);
+            let (screen_x, screen_y) = self.screen(x as f32 + 0.5 + dx, y as f32 + 0.5 + dy);
+
+            let lens_sample = if self.camera.aperture > 0.0 { (rng.gen(), rng.gen()) } else { (0.0, 0.0) };
+            let ray = self.camera.lens_ray(screen_x, screen_y, self.aspect_ratio(), lens_sample);
+
+            if let (Some((ray_origin, ray_direction)), Some(light)) = (ray, self.lights.first()) {
+                let jittered;
+                let light = if self.soft_shadows && light.radius > 0.0 {
+                    jittered = Light {
+                        position: light.position + random_in_unit_sphere(rng) * light.radius,
+                        ..light.clone()
+                    };
+                    &jittered
+                } else {
+                    light
+                };
+                match aov.as_deref_mut() {
+                    // Las pasadas reusan el impacto de esta muestra en vez de trazar otro rayo
+                    Some(aov) if centered == Some(sample) => {
+                        let (intersect, index) = trace_with_index(&ray_origin, &ray_direction, self.objects);
+                        color += shade(&ray_origin, &intersect, self.objects, light) * weight;
+                        *aov = aov_from_intersect(&intersect, index, &ray_origin, self.camera);
+                    }
+                    _ => color += cast_ray(&ray_origin, &ray_direction, self.objects, light, 0) * weight,
+                }
             }
+            weight_sum += weight;
         }
-    });
+
+        (color, weight_sum)
+    }
+}
+
+// Un solo rayo por el centro del pixel, sin lente ni sombras suaves. Devuelve el color y
+// las pasadas auxiliares del mismo impacto, sin trazar un segundo rayo.
+pub fn sample_pixel(x: usize, y: usize, width: usize, height: usize, objects: &[Arc<dyn RayIntersect>], camera: &Camera, lights: &[Light]) -> (Vector3<f32>, AovPixel) {
+    let sampler = PixelSampler {
+        width,
+        height,
+        objects,
+        camera,
+        lights,
+        filter: Filter::Box,
+        soft_shadows: false,
+    };
+    let (screen_x, screen_y) = sampler.screen(x as f32 + 0.5, y as f32 + 0.5);
+    let (Some((ray_origin, ray_direction)), Some(light)) = (camera.primary_ray(screen_x, screen_y, sampler.aspect_ratio()), lights.first()) else {
+        return (Vector3::zeros(), AovPixel::empty());
+    };
+
+    let (intersect, index) = trace_with_index(&ray_origin, &ray_direction, objects);
+    (shade(&ray_origin, &intersect, objects, light), aov_from_intersect(&intersect, index, &ray_origin, camera))
+}
+
+fn random_in_unit_sphere(rng: &mut impl Rng) -> Vector3<f32> {
+    loop {
+        let point = Vector3::new(rng.gen::<f32>(), rng.gen::<f32>(), rng.gen::<f32>()) * 2.0 - Vector3::repeat(1.0);
+        if point.magnitude_squared() <= 1.0 {
+            return point;
+        }
+    }
+}
+
+fn aov_from_intersect(intersect: &Intersect, index: Option<usize>, ray_origin: &Vector3<f32>, camera: &Camera) -> AovPixel {
+    let Some(index) = index else {
+        return AovPixel::empty();
+    };
+
+    // Profundidad en el eje de la cámara para proyecciones planas, distancia en las panorámicas
+    let depth = match camera.projection {
+        Projection::Perspective { .. } | Projection::Orthographic { .. } => (intersect.point - camera.eye).dot(&camera.basis().0),
+        _ => (intersect.point - ray_origin).magnitude(),
+    };
+    let albedo = intersect.material.get_diffuse_color(intersect.u, intersect.v);
+    // En las aristas de los cubos la normal puede venir sin normalizar
+    let normal = intersect.normal.try_normalize(1e-6).unwrap_or_else(Vector3::zeros);
+
+    AovPixel {
+        depth,
+        normal: [normal.x, normal.y, normal.z],
+        albedo: [albedo.r, albedo.g, albedo.b].map(|channel| channel as f32 / 255.0),
+        uv: [intersect.u, intersect.v],
+        object_id: index as u32 + 1,
+        material_id: intersect.material.id,
+    }
 }
 
 fn cast_shadow(
     intersect: &Intersect,
     light: &Light,
-    objects: &[Box<dyn RayIntersect>],
+    objects: &[Arc<dyn RayIntersect>],
 ) -> f32 {
     let light_dir = (light.position - intersect.point).normalize();
     let distance_to_light = (light.position - intersect.point).magnitude();
     let shadow_ray_origin = intersect.point + intersect.normal * 1e-3;
 
-    let max_objects = objects.len().min(10);
-    for object in &objects[..max_objects] {
+    // Todos los objetos dan sombra, también los bloques colocados después
+    for object in objects {
         let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
         if shadow_intersect.is_intersecting && shadow_intersect.distance < distance_to_light {
             return 0.8; 
@@ -87,49 +430,82 @@ fn refract(incident: &Vector3<f32>, normal: &Vector3<f32>, eta_t: f32) -> Vector
     }
 }
 
-pub fn cast_ray(
-    ray_origin: &Vector3<f32>,
-    ray_direction: &Vector3<f32>,
-    objects: &[Box<dyn RayIntersect>],
-    light: &Light,
-    depth: u32,
-) -> Color {
-    if depth > 1 {
-        return Color::new(0, 0, 0);  
+// Distancia de enfoque a lo que haya en el centro de la imagen, para el autoenfoque
+pub fn autofocus_distance(objects: &[Arc<dyn RayIntersect>], camera: &Camera) -> Option<f32> {
+    let (origin, direction) = camera.primary_ray(0.0, 0.0, 1.0)?;
+    let intersect = trace(&origin, &direction, objects);
+    if !intersect.is_intersecting {
+        return None;
     }
+    let (forward, _, _) = camera.basis();
+    Some((intersect.point - camera.eye).dot(&forward))
+}
+
+// Intersección más cercana del rayo con la escena
+pub fn trace(ray_origin: &Vector3<f32>, ray_direction: &Vector3<f32>, objects: &[Arc<dyn RayIntersect>]) -> Intersect {
+    trace_with_index(ray_origin, ray_direction, objects).0
+}
 
+// Como `trace`, junto con la posición en `objects` del objeto que chocó
+pub fn trace_with_index(ray_origin: &Vector3<f32>, ray_direction: &Vector3<f32>, objects: &[Arc<dyn RayIntersect>]) -> (Intersect, Option<usize>) {
     let mut closest_intersect = Intersect::empty();
+    let mut closest_index = None;
     let mut zbuffer = f32::INFINITY;
 
-    for object in objects {
+    for (index, object) in objects.iter().enumerate() {
         let intersect = object.ray_intersect(ray_origin, ray_direction);
         if intersect.is_intersecting && intersect.distance < zbuffer {
             zbuffer = intersect.distance;
             closest_intersect = intersect;
+            closest_index = Some(index);
         }
     }
 
+    (closest_intersect, closest_index)
+}
+
+// Color del rayo sin recortar, con 1.0 como blanco
+pub fn cast_ray(
+    ray_origin: &Vector3<f32>,
+    ray_direction: &Vector3<f32>,
+    objects: &[Arc<dyn RayIntersect>],
+    light: &Light,
+    depth: u32,
+) -> Vector3<f32> {
+    if depth > 1 {
+        return Vector3::zeros();
+    }
+
+    let closest_intersect = trace(ray_origin, ray_direction, objects);
+    shade(ray_origin, &closest_intersect, objects, light)
+}
+
+fn shade(ray_origin: &Vector3<f32>, closest_intersect: &Intersect, objects: &[Arc<dyn RayIntersect>], light: &Light) -> Vector3<f32> {
     if !closest_intersect.is_intersecting {
-        return Color::new(4, 12, 36);  
+        return to_vector(Color::new(4, 12, 36));
     }
 
-    let diffuse_color = closest_intersect.material.get_diffuse_color(closest_intersect.u, closest_intersect.v);
+    let diffuse_color = to_vector(closest_intersect.material.get_diffuse_color(closest_intersect.u, closest_intersect.v));
     let light_dir = (light.position - closest_intersect.point).normalize();
     let view_dir = (ray_origin - closest_intersect.point).normalize();
     let reflect_dir = reflect(&-light_dir, &closest_intersect.normal);
 
-    let shadow_intensity = cast_shadow(&closest_intersect, light, objects);
+    let shadow_intensity = cast_shadow(closest_intersect, light, objects);
     let light_intensity = light.intensity * (1.0 - shadow_intensity);
 
     let diffuse_intensity = light_dir.dot(&closest_intersect.normal).max(0.0).min(1.0);
-    let diffuse = diffuse_color.scale(closest_intersect.material.albedo[0] * diffuse_intensity * light_intensity);
+    let diffuse = diffuse_color * (closest_intersect.material.albedo[0] * diffuse_intensity * light_intensity);
 
     let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(closest_intersect.material.specular);
-    let specular = light.color.scale(closest_intersect.material.albedo[1] * specular_intensity * light_intensity);
+    let specular = to_vector(light.color) * (closest_intersect.material.albedo[1] * specular_intensity * light_intensity);
 
     diffuse + specular
 }
 
+fn to_vector(color: Color) -> Vector3<f32> {
+    Vector3::new(color.r as f32, color.g as f32, color.b as f32) / 255.0
+}
+
 fn reflect(incident: &Vector3<f32>, normal: &Vector3<f32>) -> Vector3<f32> {
     incident - 2.0 * incident.dot(normal) * normal
 }
\ No newline at end of file
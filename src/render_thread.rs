@@ -0,0 +1,384 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use crate::accumulation::Accumulator;
+use crate::aov::{AovView, Aovs};
+use crate::camera::Camera;
+use crate::denoise::Denoiser;
+use crate::framebuffer::Framebuffer;
+use crate::governor::QualityGovernor;
+use crate::light::Light;
+use crate::ray_intersect::RayIntersect;
+use crate::raytracer::{render_with_aovs, render_with_settings, RenderSettings};
+use crate::sampling::{Filter, SamplePattern};
+use crate::temporal::TemporalReprojection;
+use crate::tiles::TileScheduler;
+use crate::upscale::Upscaler;
+
+// Cuadros promediados antes de dar la imagen quieta por terminada
+pub const MAX_ACCUMULATED_FRAMES: u32 = 256;
+// Cada cuánto se manda la imagen quieta a medio terminar
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(16);
+
+// Lo que el hilo de la ventana manda al de render cada vez que algo cambia. Los objetos
+// van en `Arc`, así copiar la escena no copia los bloques.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub objects: Vec<Arc<dyn RayIntersect>>,
+    pub lights: Vec<Light>,
+    pub camera: Camera,
+    // Tamaño del render; cambia al agrandar la ventana o con otra escala
+    pub width: usize,
+    pub height: usize,
+    pub view: AovView,
+    pub denoise: bool,
+    pub reproject: bool,
+    pub upscaler: Upscaler,
+    // La cámara o la vista cambiaron: cuadro rápido al tamaño que elija el governor
+    pub moving: bool,
+    // Los objetos cambiaron: la historia de la reproyección ya no sirve
+    pub scene_changed: bool,
+    // Solo avanzó la animación: el cuadro quieto en curso se termina antes de tomar esta
+    // escena, si no ningún cuadro llegaría a completarse entre dos pasos de la animación
+    pub animation_only: bool,
+}
+
+// Cuadro listo para mostrar
+pub struct RenderedFrame {
+    pub buffer: Vec<u32>,
+    pub width: usize,
+    pub height: usize,
+    // Cámara con que se renderizó, para que lo que se dibuje encima coincida con la imagen
+    pub camera: Camera,
+    pub status: String,
+}
+
+// Renderiza en un hilo propio para que la ventana siga respondiendo. Recibe la escena por
+// un canal y devuelve los cuadros por otro; un cuadro quieto en curso se cancela apenas
+// llega una escena nueva, salvo que solo haya avanzado la animación.
+pub struct RenderThread {
+    sender: Option<Sender<Snapshot>>,
+    receiver: Receiver<RenderedFrame>,
+    cancel: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RenderThread {
+    pub fn spawn(governor: QualityGovernor, scheduler: TileScheduler) -> Self {
+        let (sender, snapshots) = mpsc::channel();
+        let (frames, receiver) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut renderer = Renderer::new(governor, scheduler, Arc::clone(&cancel), frames);
+        let handle = std::thread::Builder::new()
+            .name("render".to_string())
+            .spawn(move || renderer.run(&snapshots))
+            .unwrap();
+
+        RenderThread {
+            sender: Some(sender),
+            receiver,
+            cancel,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn submit(&self, snapshot: Snapshot) {
+        if let Some(sender) = &self.sender {
+            let cancel = !snapshot.animation_only;
+            // Si el hilo ya terminó no hay a quién avisar
+            if sender.send(snapshot).is_ok() && cancel {
+                self.cancel.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    // El último cuadro que llegó, descartando los anteriores
+    pub fn latest_frame(&self) -> Option<RenderedFrame> {
+        self.receiver.try_iter().last()
+    }
+}
+
+impl Drop for RenderThread {
+    fn drop(&mut self) {
+        // Sin el canal el hilo sale del bucle en cuanto termina lo que está haciendo
+        self.sender = None;
+        self.cancel.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// Estado que vive en el hilo de render
+struct Renderer {
+    width: usize,
+    height: usize,
+    governor: QualityGovernor,
+    scheduler: TileScheduler,
+    temporal: TemporalReprojection,
+    accumulator: Accumulator,
+    framebuffer_high: Framebuffer,
+    // Cuadros en movimiento, al tamaño que elija el governor
+    framebuffer_moving: Framebuffer,
+    // Profundidad y normales del cuadro chico para el escalado guiado
+    moving_aovs: Aovs,
+    accumulation_aovs: Aovs,
+    progressive_settings: RenderSettings,
+    cancel: Arc<AtomicBool>,
+    frames: Sender<RenderedFrame>,
+}
+
+impl Renderer {
+    // Los búferes se crean con el tamaño de la primera escena que llega
+    fn new(governor: QualityGovernor, scheduler: TileScheduler, cancel: Arc<AtomicBool>, frames: Sender<RenderedFrame>) -> Self {
+        Renderer {
+            width: 0,
+            height: 0,
+            governor,
+            scheduler,
+            temporal: TemporalReprojection::default(),
+            accumulator: Accumulator::new(0, 0),
+            framebuffer_high: Framebuffer::new(0, 0),
+            framebuffer_moving: Framebuffer::new(0, 0),
+            moving_aovs: Aovs::new(0, 0),
+            accumulation_aovs: Aovs::new(0, 0),
+            progressive_settings: RenderSettings {
+                samples_per_pixel: 1,
+                pattern: SamplePattern::Random,
+                filter: Filter::Box,
+                adaptive: None,
+                // Cada cuadro prueba otro punto de la luz, así el promedio suaviza las sombras
+                soft_shadows: true,
+            },
+            cancel,
+            frames,
+        }
+    }
+
+    fn run(&mut self, snapshots: &Receiver<Snapshot>) {
+        let mut current: Option<Snapshot> = None;
+        loop {
+            // Con la imagen convergida (o sin escena todavía) se espera a que algo cambie
+            let idle = current.is_none() || self.accumulator.frames >= MAX_ACCUMULATED_FRAMES;
+            let first = if idle {
+                match snapshots.recv() {
+                    Ok(snapshot) => Some(snapshot),
+                    Err(_) => return,
+                }
+            } else {
+                None
+            };
+            self.cancel.store(false, Ordering::Relaxed);
+
+            // Solo interesa la escena más nueva, pero sin perder los cambios de las anteriores
+            let mut update = first;
+            loop {
+                let mut snapshot = match snapshots.try_recv() {
+                    Ok(snapshot) => snapshot,
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return,
+                };
+                if let Some(previous) = &update {
+                    snapshot.moving |= previous.moving;
+                    snapshot.scene_changed |= previous.scene_changed;
+                }
+                update = Some(snapshot);
+            }
+
+            if let Some(snapshot) = update {
+                self.resize(snapshot.width, snapshot.height);
+                self.accumulator.reset();
+                if snapshot.scene_changed {
+                    self.temporal.reset();
+                }
+                let moving = snapshot.moving;
+                current = Some(snapshot);
+                if moving {
+                    let frame = self.render_moving(current.as_ref().unwrap());
+                    if self.frames.send(frame).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+            }
+
+            let Some(snapshot) = &current else {
+                continue;
+            };
+            if !self.render_still(snapshot) {
+                return;
+            }
+        }
+    }
+
+    // La imagen anterior se descarta; la reproyección sí soporta el cambio de tamaño
+    fn resize(&mut self, width: usize, height: usize) {
+        if (self.width, self.height) == (width, height) {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        self.accumulator = Accumulator::new(width, height);
+        self.framebuffer_high = Framebuffer::new(width, height);
+        self.accumulation_aovs = Aovs::new(width, height);
+    }
+
+    fn render_moving(&mut self, snapshot: &Snapshot) -> RenderedFrame {
+        let (width, height) = (self.width, self.height);
+        let (moving_width, moving_height) = self.governor.render_size(width, height);
+        if (self.framebuffer_moving.width, self.framebuffer_moving.height) != (moving_width, moving_height) {
+            self.framebuffer_moving = Framebuffer::new(moving_width, moving_height);
+            self.moving_aovs = Aovs::new(moving_width, moving_height);
+        }
+        let reprojecting = snapshot.reproject && snapshot.view == AovView::Beauty && !snapshot.denoise && TemporalReprojection::supports(&snapshot.camera);
+        // Las pasadas solo hacen falta si el escalado las usa y la imagen se agranda
+        let guide = (snapshot.upscaler.needs_guide() && moving_width < width).then_some(&mut self.moving_aovs);
+        let render_start = Instant::now();
+        if reprojecting {
+            self.temporal.render(&mut self.framebuffer_moving, &snapshot.objects, &snapshot.camera, &snapshot.lights, guide);
+        } else {
+            self.temporal.reset();
+            render_view(&mut self.framebuffer_moving, &snapshot.objects, &snapshot.camera, &snapshot.lights, &self.governor.settings(), snapshot.view, snapshot.denoise, guide);
+        }
+        let scaled_framebuffer = snapshot.upscaler.upscale(&self.framebuffer_moving, width, height, Some(&self.moving_aovs));
+        self.governor.record(render_start.elapsed());
+        // Los tiles del primer cuadro quieto se van pintando encima de este
+        self.framebuffer_high.buffer = scaled_framebuffer.buffer;
+
+        let status = if reprojecting {
+            let fresh = self.temporal.traced as f32 / (moving_width * moving_height) as f32;
+            format!("{} · {:.0}% rayos nuevos · {}", self.governor.status(), fresh * 100.0, snapshot.upscaler.name())
+        } else {
+            format!("{} · {}", self.governor.status(), snapshot.upscaler.name())
+        };
+        self.frame(snapshot, status)
+    }
+
+    // Suma un cuadro más a la imagen quieta. Devuelve false si ya nadie recibe los cuadros.
+    fn render_still(&mut self, snapshot: &Snapshot) -> bool {
+        if snapshot.view != AovView::Beauty {
+            // Las pasadas de depuración no se promedian
+            render_view(&mut self.framebuffer_high, &snapshot.objects, &snapshot.camera, &snapshot.lights, &RenderSettings::default(), snapshot.view, snapshot.denoise, None);
+            self.accumulator.frames = MAX_ACCUMULATED_FRAMES;
+            let frame = self.frame(snapshot, self.still_status(snapshot));
+            return self.frames.send(frame).is_ok();
+        }
+
+        // Las pasadas auxiliares para el denoiser salen del primer cuadro
+        let with_aovs = snapshot.denoise && self.accumulator.frames == 0;
+        if !self.render_tiles(snapshot, with_aovs) {
+            // Cancelado: la escena nueva ya está en el canal
+            return true;
+        }
+        self.accumulator.finish_frame();
+        if snapshot.denoise {
+            // Cuantos más cuadros, menos ruido queda por quitar
+            let strength = 1.0 / (self.accumulator.frames as f32).sqrt();
+            Denoiser::default().with_strength(strength).apply(&mut self.framebuffer_high, &self.accumulation_aovs);
+        }
+        let frame = self.frame(snapshot, self.still_status(snapshot));
+        self.frames.send(frame).is_ok()
+    }
+
+    // Renderiza un cuadro de la imagen quieta por tiles en otro hilo y va mandando lo que
+    // está listo. Devuelve false si el cuadro se canceló a medias.
+    fn render_tiles(&mut self, snapshot: &Snapshot, with_aovs: bool) -> bool {
+        let (width, height) = (self.width, self.height);
+        let (sender, receiver) = mpsc::channel();
+        let (scheduler, settings, cancel) = (&self.scheduler, &self.progressive_settings, &*self.cancel);
+        // Mientras dura el cuadro no cambia
+        let status = view_status(snapshot.view, self.still_status(snapshot));
+
+        std::thread::scope(|scope| {
+            let worker = scope.spawn(move || {
+                scheduler.render(width, height, &snapshot.objects, &snapshot.camera, &snapshot.lights, settings, with_aovs, cancel, &sender)
+            });
+
+            let mut last_display = Instant::now();
+            loop {
+                match receiver.recv_timeout(Duration::from_millis(4)) {
+                    Ok(rendered) => {
+                        self.accumulator.add_tile(&rendered.tile, &rendered.pixels, &mut self.framebuffer_high);
+                        if let Some(tile_aovs) = rendered.aovs {
+                            for (row, pixels) in tile_aovs.chunks(rendered.tile.width).enumerate() {
+                                let start = (rendered.tile.y + row) * width + rendered.tile.x;
+                                self.accumulation_aovs.pixels[start..start + rendered.tile.width].copy_from_slice(pixels);
+                            }
+                        }
+                    }
+                    // El hilo terminó y soltó el canal: ya llegaron todos los tiles
+                    Err(RecvTimeoutError::Disconnected) => break,
+                    Err(RecvTimeoutError::Timeout) => {}
+                }
+
+                if last_display.elapsed() >= PROGRESS_INTERVAL && !cancel.load(Ordering::Relaxed) {
+                    let frame = RenderedFrame {
+                        buffer: self.framebuffer_high.buffer.clone(),
+                        width,
+                        height,
+                        camera: snapshot.camera.clone(),
+                        status: status.clone(),
+                    };
+                    if self.frames.send(frame).is_err() {
+                        cancel.store(true, Ordering::Relaxed);
+                    }
+                    last_display = Instant::now();
+                }
+            }
+
+            worker.join().unwrap()
+        })
+    }
+
+    fn still_status(&self, snapshot: &Snapshot) -> String {
+        if self.accumulator.frames == 0 {
+            format!("{} · {}", self.governor.status(), snapshot.upscaler.name())
+        } else {
+            format!("{} cuadros acumulados", self.accumulator.frames)
+        }
+    }
+
+    fn frame(&self, snapshot: &Snapshot, status: String) -> RenderedFrame {
+        RenderedFrame {
+            buffer: self.framebuffer_high.buffer.clone(),
+            width: self.width,
+            height: self.height,
+            camera: snapshot.camera.clone(),
+            status: view_status(snapshot.view, status),
+        }
+    }
+}
+
+// En las pasadas de depuración el título dice cuál se está viendo
+fn view_status(view: AovView, status: String) -> String {
+    match view {
+        AovView::Beauty => status,
+        _ => format!("{} - {}", view.name(), status),
+    }
+}
+
+// Renderiza la imagen normal o, en modo depuración, la pasada elegida en falso color.
+// Con `aovs` las pasadas auxiliares quedan ahí aunque no se muestren.
+#[allow(clippy::too_many_arguments)]
+pub fn render_view(framebuffer: &mut Framebuffer, objects: &[Arc<dyn RayIntersect>], camera: &Camera, lights: &[Light], settings: &RenderSettings, view: AovView, denoise: bool, aovs: Option<&mut Aovs>) {
+    if view == AovView::Beauty && !denoise && aovs.is_none() {
+        render_with_settings(framebuffer, objects, camera, lights, settings);
+        return;
+    }
+    let mut own_aovs;
+    let aovs = match aovs {
+        Some(aovs) => aovs,
+        None => {
+            own_aovs = Aovs::new(framebuffer.width, framebuffer.height);
+            &mut own_aovs
+        }
+    };
+    render_with_aovs(framebuffer, objects, camera, lights, settings, Some(&mut *aovs));
+    match view {
+        AovView::Beauty if denoise => Denoiser::default().apply(framebuffer, aovs),
+        AovView::Beauty => {}
+        _ => framebuffer.buffer = aovs.visualize(view).buffer,
+    }
+}
@@ -0,0 +1,116 @@
+use rand::Rng;
+
+// Cómo se reparten las muestras de un pixel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SamplePattern {
+    // Posiciones al azar en todo el pixel
+    Random,
+    // Rejilla N×N con una muestra en el centro de cada celda
+    Stratified,
+    // Rejilla N×N con una muestra al azar dentro de cada celda
+    Jittered,
+}
+
+// Filtro de reconstrucción: cuánto pesa cada muestra según su distancia al centro del pixel.
+// Los filtros más anchos que el pixel toman muestras que también caen en los vecinos.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    Box,
+    Tent,
+    Gaussian,
+    // Mitchell-Netravali con B = C = 1/3
+    Mitchell,
+}
+
+impl Filter {
+    // Radio del soporte en pixeles
+    pub fn radius(self) -> f32 {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian => 1.5,
+            Filter::Mitchell => 2.0,
+        }
+    }
+
+    pub fn weight(self, dx: f32, dy: f32) -> f32 {
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+
+    fn weight_1d(self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            Filter::Box => 1.0,
+            Filter::Tent => (1.0 - x).max(0.0),
+            Filter::Gaussian => {
+                let alpha = 2.0;
+                let radius = self.radius();
+                ((-alpha * x * x).exp() - (-alpha * radius * radius).exp()).max(0.0)
+            }
+            Filter::Mitchell => {
+                let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+                if x < 1.0 {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x * x * x + (-18.0 + 12.0 * b + 6.0 * c) * x * x + (6.0 - 2.0 * b)) / 6.0
+                } else if x < 2.0 {
+                    ((-b - 6.0 * c) * x * x * x + (6.0 * b + 30.0 * c) * x * x + (-12.0 * b - 48.0 * c) * x + (8.0 * b + 24.0 * c)) / 6.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Filter> {
+        match name {
+            "box" => Some(Filter::Box),
+            "tent" => Some(Filter::Tent),
+            "gaussian" => Some(Filter::Gaussian),
+            "mitchell" => Some(Filter::Mitchell),
+            _ => None,
+        }
+    }
+}
+
+impl SamplePattern {
+    pub fn from_name(name: &str) -> Option<SamplePattern> {
+        match name {
+            "random" => Some(SamplePattern::Random),
+            "stratified" => Some(SamplePattern::Stratified),
+            "jittered" => Some(SamplePattern::Jittered),
+            _ => None,
+        }
+    }
+
+    // Desplazamientos desde el centro del pixel, repartidos en el soporte del filtro.
+    // Las rejillas usan N×N muestras con N = ⌈√samples⌉.
+    pub fn offsets(self, samples: u32, filter: Filter, rng: &mut impl Rng) -> Vec<(f32, f32)> {
+        let radius = filter.radius();
+        let samples = samples.max(1);
+        match self {
+            SamplePattern::Random => (0..samples)
+                .map(|_| ((rng.gen::<f32>() * 2.0 - 1.0) * radius, (rng.gen::<f32>() * 2.0 - 1.0) * radius))
+                .collect(),
+            SamplePattern::Stratified | SamplePattern::Jittered => {
+                let n = (samples as f32).sqrt().ceil() as u32;
+                let cell = 2.0 * radius / n as f32;
+                let mut offsets = Vec::with_capacity((n * n) as usize);
+                for j in 0..n {
+                    for i in 0..n {
+                        let (u, v): (f32, f32) = if self == SamplePattern::Jittered { (rng.gen(), rng.gen()) } else { (0.5, 0.5) };
+                        offsets.push((-radius + (i as f32 + u) * cell, -radius + (j as f32 + v) * cell));
+                    }
+                }
+                offsets
+            }
+        }
+    }
+}
+
+// Muestras extra donde el contraste con los vecinos es alto (bordes de bloques, sombras)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adaptive {
+    // Diferencia de luminancia (0 a 1) en la vecindad 3×3 a partir de la cual se refina
+    pub threshold: f32,
+    // Muestras adicionales en los pixeles que pasan el umbral
+    pub extra_samples: u32,
+}
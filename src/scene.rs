@@ -0,0 +1,1083 @@
+use nalgebra::{Matrix4, Vector3};
+use std::f32::consts::PI;
+use std::fs;
+use std::sync::Arc;
+use crate::block_model::{BlockModel, BlockShape, Connections, Facing, Half};
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cross::CrossBlock;
+use crate::cube::Cube;
+use crate::instance::Instance;
+use crate::light::Light;
+use crate::ray_intersect::{Material, RayIntersect};
+use crate::texture::Texture;
+
+pub struct Scene {
+    pub objects: Vec<Arc<dyn RayIntersect>>,
+    pub lights: Vec<Light>,
+    pub camera: Camera,
+    // Materiales con nombre, en el orden en que se declararon
+    pub materials: Vec<(String, Material)>,
+}
+
+// Materiales que trae el proyecto; los archivos de escena los usan por nombre. Las texturas
+// se buscan relativas al directorio actual, así que falla si no se corre desde el proyecto.
+pub fn builtin_materials() -> Result<Vec<(String, Material)>, String> {
+    let agua_texture = Texture::try_load("assets/agua.jpg")?;
+    let tierra_texture = Texture::try_load("assets/tierra.jpeg")?;
+    let tierra_grama_texture = Texture::try_load("assets/tierra2.png")?;
+    let grama_texture = Texture::try_load("assets/grama.png")?;
+    let madera_texture = Texture::try_load("assets/madera.jpg")?;
+    let hoja_texture = Texture::try_load("assets/hoja_arbol.jpg")?;
+    let piedra_texture = Texture::try_load("assets/piedra.png")?;
+    let arena_texture = Texture::try_load("assets/arena.png")?;
+    // Con fondo transparente, para los planos cruzados
+    let planta_texture = Texture::try_load("assets/planta.png")?;
+
+    // Definimos los materiales 
+    let tierra_grama = Material::new(Color::new(255, 255, 255), 32.0, [0.9, 0.1, 0.0, 0.0], 1.0, true, Some(tierra_grama_texture.clone()));
+    let tierra = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(tierra_texture.clone()));
+    let grama = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(grama_texture.clone()));
+    let agua = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(agua_texture.clone())).non_solid();
+    let madera = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(madera_texture.clone()));
+    let piedra = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(piedra_texture.clone()));
+    let hoja = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(hoja_texture.clone()));
+    let arena = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(arena_texture.clone()));
+    let planta = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(planta_texture.clone())).non_solid();
+    let glass = Material::new(Color::new(255, 255, 255), 125.0, [0.0, 0.5, 0.1, 0.8], 1.5, false, None); // Vidrio, 80% transparente, índice de refracción 1.5
+
+    let mut materials = vec![
+        ("tierra".to_string(), tierra),
+        ("tierra_grama".to_string(), tierra_grama),
+        ("grama".to_string(), grama),
+        ("arena".to_string(), arena),
+        ("piedra".to_string(), piedra),
+        ("madera".to_string(), madera),
+        ("hoja".to_string(), hoja),
+        ("agua".to_string(), agua),
+        ("vidrio".to_string(), glass),
+        ("planta".to_string(), planta),
+    ];
+    for (index, (_, material)) in materials.iter_mut().enumerate() {
+        material.id = index as u32 + 1;
+    }
+    Ok(materials)
+}
+
+impl Scene {
+    pub fn material(&self, name: &str) -> Option<&Material> {
+        self.materials.iter().find(|(material_name, _)| material_name == name).map(|(_, material)| material)
+    }
+
+    // El diorama de Minecraft del proyecto
+    pub fn diorama() -> Result<Scene, String> {
+        let materials = builtin_materials()?;
+        let find = |name: &str| materials.iter().find(|(n, _)| n == name).map(|(_, m)| m.clone()).unwrap();
+        let tierra_grama = find("tierra_grama");
+        let tierra = find("tierra");
+        let grama = find("grama");
+        let agua = find("agua");
+        let madera = find("madera");
+        let piedra = find("piedra");
+        let hoja = find("hoja");
+        let arena = find("arena");
+        let planta = find("planta");
+
+        let camera = Camera::new(
+            Vector3::new(0.0, 5.0, -10.0),  
+            Vector3::new(0.0, 0.0, 0.0), 
+            Vector3::new(0.0, 1.0, 0.0),  
+        );
+
+        // Definimos la luz
+        let lights = vec![
+            Light::new(Vector3::new(100.0, 100.0, -100.0), Color::new(255, 255, 255), 2.0, 5.0), 
+            Light::new(Vector3::new(-100.0, -100.0, 100.0), Color::new(255, 255, 255), 2.0, 5.0),
+        ];
+
+        let mut objects: Vec<Arc<dyn RayIntersect>> = Vec::new();
+
+        // Base de 8x8 cubos
+        let grid_size = 8;
+        let cube_size = 1.0;
+    
+        for z in 0..grid_size {
+            for x in 0..grid_size {
+                let x_pos = x as f32 * cube_size - (grid_size as f32 / 2.0) * cube_size;
+                let z_pos = z as f32 * cube_size - (grid_size as f32 / 2.0) * cube_size;
+            
+                objects.push(Arc::new(Cube {
+                    center: Vector3::new(x_pos, 0.0, z_pos),  
+                    size: cube_size,                        
+                    offset: Vector3::zeros(),
+                    materials: [
+                        tierra.clone(),  // Derecha
+                        tierra.clone(),  // Izquierda
+                        tierra.clone(),  // Abajo
+                        tierra.clone(),  // Arriba
+                        tierra.clone(),  // Frente
+                        tierra.clone(),  // Atrás
+                    ],
+                }));
+            }
+        }
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 1.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 1.0, 2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 1.0, 1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 1.0, 1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 1.0, 0.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 1.0, 0.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 1.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(0.0, 1.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(0.0, 1.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-1.0, 1.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-1.0, 1.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-2.0, 1.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-2.0, 1.0, -4.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-3.0, 1.0, -4.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 1.0, -4.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 1.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 1.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 1.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-3.0, 1.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-3.0, 1.0, 0.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-2.0, 1.0, 0.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-2.0, 1.0, 1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-1.0, 1.0, 1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-1.0, 1.0, 2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(0.0, 1.0, 2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(0.0, 1.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 1.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 1.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(), arena.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 1.0, 2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 1.0, 2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 1.0, 1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(0.0, 1.0, 1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(0.0, 1.0, 0.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-1.0, 1.0, 0.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-1.0, 1.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-2.0, 1.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-2.0, 1.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-3.0, 1.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-3.0, 1.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 1.0, 0.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 1.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 1.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 1.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 1.0, -4.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 1.0, -4.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 1.0, -4.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(0.0, 1.0, -4.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-1.0, 1.0, -4.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 1.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 1.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 1.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 1.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(0.0, 1.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra.clone(), tierra.clone(), tierra.clone(), grama.clone(), tierra.clone(), tierra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 2.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 2.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 2.0, -4.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 2.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 2.0, -4.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 2.0, -4.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                tierra_grama.clone(), tierra_grama.clone(), tierra.clone(), grama.clone(), tierra_grama.clone(), tierra_grama.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-1.0, 1.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-2.0, 1.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-3.0, 1.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 1.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 1.0, 2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 1.0, 1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 1.0, 0.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-3.0, 1.0, 1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-2.0, 1.0, 2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-2.0, 2.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-3.0, 2.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-3.0, 2.0, 2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 2.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 2.0, 2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 2.0, 1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-3.0, 3.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 3.0, 3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(-4.0, 3.0, 2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 2.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 3.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 6.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 5.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 5.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 5.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 5.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 4.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(2.0, 4.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 4.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 4.0, -2.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 4.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 4.0, -1.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(1.0, 4.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+        objects.push(Arc::new(Cube {
+            center: Vector3::new(3.0, 4.0, -3.0),  
+            size: 1.0,                          
+            offset: Vector3::zeros(),
+            materials: [
+                hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(),hoja.clone(), hoja.clone(),
+            ],
+        }));
+
+        // Cajas de madera giradas: una sola geometría compartida por varias instancias
+        let caja: Arc<dyn RayIntersect> = Arc::new(Cube::new(
+            Vector3::new(0.0, 0.0, 0.0),
+            0.6,
+            [madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone()],
+        ));
+        for (posicion, angulo) in [
+            (Vector3::new(-1.0, 1.8, -3.0), PI / 6.0),
+            (Vector3::new(-3.0, 1.8, 0.0), -PI / 5.0),
+            (Vector3::new(0.0, 1.8, -3.0), PI / 3.0),
+        ] {
+            let transform = Matrix4::new_translation(&posicion) * Matrix4::new_rotation(Vector3::new(0.0, angulo, 0.0));
+            objects.push(Arc::new(Instance::new(caja.clone(), transform)));
+        }
+        // Tabla: la misma caja escalada de forma no uniforme
+        let tabla = Matrix4::new_translation(&Vector3::new(1.0, 1.56, -2.0))
+            * Matrix4::new_rotation(Vector3::new(0.0, PI / 8.0, 0.0))
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(1.5, 0.2, 0.8));
+        objects.push(Arc::new(Instance::new(caja.clone(), tabla)));
+
+        // Bloques que no son cubos completos
+        objects.push(Arc::new(BlockModel::new(
+            Vector3::new(-2.0, 2.0, 2.0),
+            1.0,
+            BlockShape::Stairs(Facing::West, Half::Bottom),
+            [piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone()],
+        )));
+        objects.push(Arc::new(BlockModel::new(
+            Vector3::new(-1.0, 2.0, 3.0),
+            1.0,
+            BlockShape::Slab(Half::Bottom),
+            [piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone()],
+        )));
+        objects.push(Arc::new(BlockModel::new(
+            Vector3::new(-4.0, 2.0, 0.0),
+            1.0,
+            BlockShape::Wall(Connections::new(false, true, false, false)),
+            [piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone(), piedra.clone()],
+        )));
+        for (z, conexiones) in [
+            (0.0, Connections::new(true, false, false, false)),
+            (-1.0, Connections::new(true, true, false, false)),
+        ] {
+            objects.push(Arc::new(BlockModel::new(
+                Vector3::new(3.0, 2.0, z),
+                1.0,
+                BlockShape::Fence(conexiones),
+                [madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone(), madera.clone()],
+            )));
+        }
+        objects.push(Arc::new(BlockModel::new(
+            Vector3::new(1.0, 2.0, 0.0),
+            1.0,
+            BlockShape::Carpet,
+            [hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone(), hoja.clone()],
+        )));
+        objects.push(Arc::new(BlockModel::new(
+            Vector3::new(-1.0, 2.0, 1.0),
+            1.0,
+            BlockShape::Fluid(6),
+            [agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone(), agua.clone()],
+        )));
+
+        // Brotes y plantas: planos cruzados que se mecen con el viento
+        for posicion in [
+            Vector3::new(3.0, 3.0, -4.0),
+            Vector3::new(1.0, 3.0, -4.0),
+            Vector3::new(1.0, 2.0, -3.0),
+            Vector3::new(2.0, 2.0, -1.0),
+        ] {
+            objects.push(Arc::new(CrossBlock::new(posicion, 1.0, planta.clone()).with_random_offset().with_sway(0.12)));
+        }
+
+        Ok(Scene {
+            objects,
+            lights,
+            camera,
+            materials,
+        })
+    }
+
+    // Carga una escena de texto. Cada línea es un comando; `#` empieza un comentario.
+    // Los materiales se nombran con los de `builtin_materials` o con `material`.
+    //
+    //   camera ex ey ez cx cy cz
+    //   light x y z r g b intensity radius
+    //   material name texture_path
+    //   cube x y z size mat [mat x5]         (derecha, izquierda, abajo, arriba, frente, atrás)
+    //   slab x y z size bottom|top mat
+    //   stairs x y z size north|south|east|west bottom|top mat
+    //   fence|wall|pane x y z size nsew|- mat   (letras de los lados conectados)
+    //   carpet x y z size mat
+    //   fluid x y z size level mat
+    //   cross x y z size mat [sway]
+    pub fn load(path: &str) -> Result<Scene, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+        let mut scene = Scene {
+            objects: Vec::new(),
+            lights: Vec::new(),
+            camera: Camera::new(Vector3::new(0.0, 5.0, -10.0), Vector3::zeros(), Vector3::new(0.0, 1.0, 0.0)),
+            materials: builtin_materials()?,
+        };
+
+        for (line_number, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            scene
+                .parse_line(line)
+                .map_err(|e| format!("{}:{}: {}", path, line_number + 1, e))?;
+        }
+
+        Ok(scene)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let command = tokens[0];
+        let args = &tokens[1..];
+
+        let number = |index: usize| -> Result<f32, String> {
+            let token = args.get(index).ok_or_else(|| format!("a `{}` le faltan argumentos", command))?;
+            token.parse::<f32>().map_err(|_| format!("número inválido `{}`", token))
+        };
+        let vector = |index: usize| -> Result<Vector3<f32>, String> {
+            Ok(Vector3::new(number(index)?, number(index + 1)?, number(index + 2)?))
+        };
+        let word = |index: usize| -> Result<&str, String> {
+            args.get(index).copied().ok_or_else(|| format!("a `{}` le faltan argumentos", command))
+        };
+
+        match command {
+            "camera" => {
+                self.camera = Camera::new(vector(0)?, vector(3)?, Vector3::new(0.0, 1.0, 0.0));
+            }
+            "light" => {
+                let color = Color::new(number(3)? as u8, number(4)? as u8, number(5)? as u8);
+                self.lights.push(Light::new(vector(0)?, color, number(6)?, number(7)?));
+            }
+            "material" => {
+                let name = word(0)?.to_string();
+                let texture_path = word(1)?;
+                let texture = Texture::try_load(texture_path)?;
+                let mut material = Material::new(Color::new(255, 255, 255), 32.0, [1.0, 0.1, 0.0, 0.0], 1.0, true, Some(texture));
+                material.id = self.materials.iter().map(|(_, existing)| existing.id).max().unwrap_or(0) + 1;
+                self.materials.retain(|(existing, _)| existing != &name);
+                self.materials.push((name, material));
+            }
+            "cube" => {
+                let materials = match args.len() {
+                    5 => self.faces(word(4)?)?,
+                    10 => [
+                        self.lookup(word(4)?)?, self.lookup(word(5)?)?, self.lookup(word(6)?)?,
+                        self.lookup(word(7)?)?, self.lookup(word(8)?)?, self.lookup(word(9)?)?,
+                    ],
+                    _ => return Err("`cube` lleva 1 o 6 materiales".to_string()),
+                };
+                self.objects.push(Arc::new(Cube::new(vector(0)?, number(3)?, materials)));
+            }
+            "slab" | "stairs" | "fence" | "wall" | "pane" | "carpet" | "fluid" => {
+                let (shape, material_index) = match command {
+                    "slab" => (BlockShape::Slab(parse_half(word(4)?)?), 5),
+                    "stairs" => (BlockShape::Stairs(parse_facing(word(4)?)?, parse_half(word(5)?)?), 6),
+                    "fence" => (BlockShape::Fence(parse_connections(word(4)?)?), 5),
+                    "wall" => (BlockShape::Wall(parse_connections(word(4)?)?), 5),
+                    "pane" => (BlockShape::Pane(parse_connections(word(4)?)?), 5),
+                    "carpet" => (BlockShape::Carpet, 4),
+                    _ => (BlockShape::Fluid(number(4)?.clamp(0.0, 7.0) as u8), 5),
+                };
+                let materials = self.faces(word(material_index)?)?;
+                self.objects.push(Arc::new(BlockModel::new(vector(0)?, number(3)?, shape, materials)));
+            }
+            "cross" => {
+                let material = self.lookup(word(4)?)?;
+                let sway = if args.len() > 5 { number(5)? } else { 0.0 };
+                let cross = CrossBlock::new(vector(0)?, number(3)?, material).with_random_offset().with_sway(sway);
+                self.objects.push(Arc::new(cross));
+            }
+            _ => return Err(format!("comando desconocido `{}`", command)),
+        }
+
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Result<Material, String> {
+        self.material(name).cloned().ok_or_else(|| format!("material desconocido `{}`", name))
+    }
+
+    fn faces(&self, name: &str) -> Result<[Material; 6], String> {
+        let material = self.lookup(name)?;
+        Ok([material.clone(), material.clone(), material.clone(), material.clone(), material.clone(), material])
+    }
+}
+
+fn parse_half(token: &str) -> Result<Half, String> {
+    match token {
+        "bottom" => Ok(Half::Bottom),
+        "top" => Ok(Half::Top),
+        _ => Err(format!("se esperaba `bottom` o `top`, se recibió `{}`", token)),
+    }
+}
+
+fn parse_facing(token: &str) -> Result<Facing, String> {
+    match token {
+        "north" => Ok(Facing::North),
+        "south" => Ok(Facing::South),
+        "east" => Ok(Facing::East),
+        "west" => Ok(Facing::West),
+        _ => Err(format!("se esperaba una orientación (north, south, east, west), se recibió `{}`", token)),
+    }
+}
+
+fn parse_connections(token: &str) -> Result<Connections, String> {
+    if token == "-" {
+        return Ok(Connections::default());
+    }
+    if let Some(invalid) = token.chars().find(|c| !"nsew".contains(*c)) {
+        return Err(format!("conexión inválida `{}` (usar n, s, e, w o -)", invalid));
+    }
+    Ok(Connections::new(token.contains('n'), token.contains('s'), token.contains('e'), token.contains('w')))
+}
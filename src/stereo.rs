@@ -0,0 +1,108 @@
+use nalgebra::Vector3;
+use crate::camera::{Camera, Stereo, StereoLayout};
+use crate::framebuffer::{Framebuffer, HdrFramebuffer};
+use crate::light::Light;
+use crate::ray_intersect::RayIntersect;
+use crate::raytracer::{render_hdr, render_with_settings, RenderSettings};
+use std::sync::Arc;
+
+// Zona de la imagen estéreo que ve un ojo, con la cámara de ese ojo. En el anaglifo los
+// dos ojos ocupan la imagen entera.
+pub struct EyeViewport {
+    pub camera: Camera,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl EyeViewport {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+}
+
+// (izquierdo, derecho) en una imagen de `width`×`height`
+pub fn eye_viewports(camera: &Camera, stereo: &Stereo, width: usize, height: usize) -> [EyeViewport; 2] {
+    let viewport = |side: f32, x: usize, y: usize, width: usize, height: usize| EyeViewport {
+        camera: camera.eye_camera(side),
+        x,
+        y,
+        width,
+        height,
+    };
+    match stereo.layout {
+        StereoLayout::SideBySide => [viewport(-1.0, 0, 0, width / 2, height), viewport(1.0, width / 2, 0, width - width / 2, height)],
+        StereoLayout::OverUnder => [viewport(-1.0, 0, 0, width, height / 2), viewport(1.0, 0, height / 2, width, height - height / 2)],
+        StereoLayout::Anaglyph => [viewport(-1.0, 0, 0, width, height), viewport(1.0, 0, 0, width, height)],
+    }
+}
+
+// Renderiza cada ojo con `render_with_settings()` y los junta en el framebuffer según el formato
+pub fn render_stereo(framebuffer: &mut Framebuffer, objects: &[Arc<dyn RayIntersect>], camera: &Camera, lights: &[Light], stereo: &Stereo, settings: &RenderSettings) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let render_eye = |eye: &EyeViewport| {
+        let mut framebuffer = Framebuffer::new(eye.width, eye.height);
+        render_with_settings(&mut framebuffer, objects, &eye.camera, lights, settings);
+        framebuffer.buffer
+    };
+    compose(&mut framebuffer.buffer, width, height, camera, stereo, render_eye, anaglyph);
+}
+
+// Lo mismo con `render_hdr()`, sin recortar el color
+pub fn render_stereo_hdr(framebuffer: &mut HdrFramebuffer, objects: &[Arc<dyn RayIntersect>], camera: &Camera, lights: &[Light], stereo: &Stereo, settings: &RenderSettings) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let render_eye = |eye: &EyeViewport| {
+        let mut framebuffer = HdrFramebuffer::new(eye.width, eye.height);
+        render_hdr(&mut framebuffer, objects, &eye.camera, lights, settings, None);
+        framebuffer.buffer
+    };
+    let anaglyph = |left: [f32; 3], right: [f32; 3]| anaglyph_color(&left.into(), &right.into()).into();
+    compose(&mut framebuffer.buffer, width, height, camera, stereo, render_eye, anaglyph);
+}
+
+fn compose<T: Copy>(
+    buffer: &mut [T],
+    width: usize,
+    height: usize,
+    camera: &Camera,
+    stereo: &Stereo,
+    render_eye: impl Fn(&EyeViewport) -> Vec<T>,
+    anaglyph: impl Fn(T, T) -> T,
+) {
+    let [left, right] = eye_viewports(camera, stereo, width, height);
+    let (left_pixels, right_pixels) = (render_eye(&left), render_eye(&right));
+    match stereo.layout {
+        StereoLayout::Anaglyph => {
+            for ((pixel, &l), &r) in buffer.iter_mut().zip(&left_pixels).zip(&right_pixels) {
+                *pixel = anaglyph(l, r);
+            }
+        }
+        StereoLayout::SideBySide | StereoLayout::OverUnder => {
+            blit(buffer, width, &left_pixels, &left);
+            blit(buffer, width, &right_pixels, &right);
+        }
+    }
+}
+
+fn blit<T: Copy>(target: &mut [T], target_width: usize, source: &[T], eye: &EyeViewport) {
+    for (y, row) in source.chunks(eye.width.max(1)).enumerate() {
+        let target_start = (y + eye.y) * target_width + eye.x;
+        target[target_start..target_start + eye.width].copy_from_slice(row);
+    }
+}
+
+// Anaglifo de medio color: el rojo sale de la luminancia del ojo izquierdo para
+// reducir la rivalidad retinal, verde y azul del derecho
+fn anaglyph(left: u32, right: u32) -> u32 {
+    let r = ((left >> 16) & 0xFF) as f32;
+    let g = ((left >> 8) & 0xFF) as f32;
+    let b = (left & 0xFF) as f32;
+    let luminance = (0.299 * r + 0.587 * g + 0.114 * b).min(255.0) as u32;
+    (luminance << 16) | (right & 0x00FFFF)
+}
+
+// Igual que `anaglyph` con colores sin recortar, donde 1.0 es blanco
+pub fn anaglyph_color(left: &Vector3<f32>, right: &Vector3<f32>) -> Vector3<f32> {
+    Vector3::new(0.299 * left.x + 0.587 * left.y + 0.114 * left.z, right.y, right.z)
+}
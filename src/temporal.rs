@@ -0,0 +1,175 @@
+use nalgebra::Vector3;
+use rayon::prelude::*;
+use crate::aov::{AovPixel, Aovs};
+use crate::camera::{Camera, Projection};
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::ray_intersect::RayIntersect;
+use crate::raytracer::sample_pixel;
+use std::sync::Arc;
+
+// Orden en que se visitan los pixeles de cada celda 2×2: cada pixel recibe un rayo
+// nuevo una vez cada cuatro cuadros
+const PATTERN: [(usize, usize); 4] = [(0, 0), (1, 1), (1, 0), (0, 1)];
+// El cielo no tiene profundidad; se reproyecta como si estuviera a esta distancia
+const SKY_DEPTH: f32 = 1000.0;
+
+// Reutiliza el cuadro anterior mientras la cámara se mueve (al estilo del TAA). Cada
+// pixel viejo se lleva al mundo con su profundidad y la cámara anterior y se proyecta
+// con la nueva. Solo se trazan rayos en un cuarto de los pixeles y en los huecos que
+// deja la reproyección; la historia se recorta al rango de los rayos nuevos vecinos
+// para que no queden fantasmas.
+pub struct TemporalReprojection {
+    // Peso de la historia al mezclarla con un rayo nuevo
+    pub history_weight: f32,
+    // Rayos trazados en el último cuadro
+    pub traced: usize,
+    width: usize,
+    height: usize,
+    // Color y pasadas auxiliares de cada pixel del cuadro anterior
+    history: Vec<(Vector3<f32>, AovPixel)>,
+    camera: Option<Camera>,
+    frame: usize,
+}
+
+impl Default for TemporalReprojection {
+    fn default() -> Self {
+        TemporalReprojection {
+            history_weight: 0.7,
+            traced: 0,
+            width: 0,
+            height: 0,
+            history: Vec::new(),
+            camera: None,
+            frame: 0,
+        }
+    }
+}
+
+impl TemporalReprojection {
+    // Solo las proyecciones planas se pueden invertir; la lente y el estéreo no se reproyectan
+    pub fn supports(camera: &Camera) -> bool {
+        let planar = matches!(camera.projection, Projection::Perspective { .. } | Projection::Orthographic { .. });
+        planar && camera.stereo.is_none() && camera.aperture <= 0.0
+    }
+
+    // Descarta la historia, por ejemplo cuando la escena cambió
+    pub fn reset(&mut self) {
+        self.camera = None;
+    }
+
+    // Con `aovs` también deja ahí las pasadas auxiliares reproyectadas, del tamaño del framebuffer
+    pub fn render(&mut self, framebuffer: &mut Framebuffer, objects: &[Arc<dyn RayIntersect>], camera: &Camera, lights: &[Light], aovs: Option<&mut Aovs>) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let history = self.reproject(camera, width, height);
+        let offset = PATTERN[self.frame % PATTERN.len()];
+        self.frame += 1;
+
+        let fresh: Vec<Option<(Vector3<f32>, AovPixel)>> = (0..width * height)
+            .into_par_iter()
+            .map(|index| {
+                let (x, y) = (index % width, index / width);
+                let scheduled = (x % 2, y % 2) == offset;
+                (scheduled || history[index].is_none()).then(|| sample_pixel(x, y, width, height, objects, camera, lights))
+            })
+            .collect();
+        self.traced = fresh.iter().filter(|sample| sample.is_some()).count();
+
+        let resolved: Vec<(Vector3<f32>, AovPixel)> = (0..width * height)
+            .into_par_iter()
+            .map(|index| {
+                let Some((color, aov)) = history[index] else {
+                    return fresh[index].unwrap_or((Vector3::zeros(), AovPixel::empty()));
+                };
+                let color = match neighborhood_bounds(&fresh, index % width, index / width, width, height) {
+                    Some((min, max)) => color.sup(&min).inf(&max),
+                    None => color,
+                };
+                match fresh[index] {
+                    Some((fresh_color, fresh_aov)) => (color.lerp(&fresh_color, 1.0 - self.history_weight), fresh_aov),
+                    None => (color, aov),
+                }
+            })
+            .collect();
+
+        for (pixel, (color, _)) in framebuffer.buffer.iter_mut().zip(&resolved) {
+            let [r, g, b] = [color.x, color.y, color.z].map(|channel| (channel * 255.0).round().clamp(0.0, 255.0) as u32);
+            *pixel = (r << 16) | (g << 8) | b;
+        }
+
+        if let Some(aovs) = aovs {
+            *aovs = Aovs {
+                width,
+                height,
+                pixels: resolved.iter().map(|&(_, aov)| aov).collect(),
+            };
+        }
+
+        self.width = width;
+        self.height = height;
+        self.history = resolved;
+        self.camera = Some(camera.clone());
+    }
+
+    // Historia vista desde `camera` en una imagen de `width`×`height` (puede tener otro
+    // tamaño que el cuadro anterior). Cuando dos pixeles caen en el mismo gana el más cercano.
+    fn reproject(&self, camera: &Camera, width: usize, height: usize) -> Vec<Option<(Vector3<f32>, AovPixel)>> {
+        let mut history = vec![None; width * height];
+        let Some(previous) = &self.camera else {
+            return history;
+        };
+
+        let (forward, _, _) = previous.basis();
+        let previous_aspect = self.width as f32 / self.height as f32;
+        let aspect = width as f32 / height as f32;
+
+        let targets: Vec<Option<(usize, f32)>> = (0..self.width * self.height)
+            .into_par_iter()
+            .map(|index| {
+                let (x, y) = (index % self.width, index / self.width);
+                let screen_x = (2.0 * (x as f32 + 0.5)) / self.width as f32 - 1.0;
+                let screen_y = -((2.0 * (y as f32 + 0.5)) / self.height as f32 - 1.0);
+                let (origin, direction) = previous.primary_ray(screen_x, screen_y, previous_aspect)?;
+                let depth = self.history[index].1.depth.min(SKY_DEPTH);
+                let point = origin + direction * (depth / direction.dot(&forward).max(1e-4));
+
+                let (screen_x, screen_y, depth) = camera.project(&point, aspect)?;
+                let pixel_x = (screen_x + 1.0) / 2.0 * width as f32;
+                let pixel_y = (1.0 - screen_y) / 2.0 * height as f32;
+                if pixel_x < 0.0 || pixel_y < 0.0 || pixel_x >= width as f32 || pixel_y >= height as f32 {
+                    return None;
+                }
+                Some((pixel_y as usize * width + pixel_x as usize, depth))
+            })
+            .collect();
+
+        for (index, target) in targets.into_iter().enumerate() {
+            if let Some((target, depth)) = target {
+                if history[target].is_none_or(|(_, closest): (Vector3<f32>, AovPixel)| depth < closest.depth) {
+                    let (color, aov) = self.history[index];
+                    // El cielo sigue sin profundidad
+                    let depth = if aov.object_id == 0 { f32::INFINITY } else { depth };
+                    history[target] = Some((color, AovPixel { depth, ..aov }));
+                }
+            }
+        }
+
+        history
+    }
+}
+
+// Mínimo y máximo por canal de los rayos nuevos en la vecindad 3×3
+fn neighborhood_bounds(fresh: &[Option<(Vector3<f32>, AovPixel)>], x: usize, y: usize, width: usize, height: usize) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    let mut bounds: Option<(Vector3<f32>, Vector3<f32>)> = None;
+    for ny in y.saturating_sub(1)..(y + 2).min(height) {
+        for nx in x.saturating_sub(1)..(x + 2).min(width) {
+            if let Some((color, _)) = fresh[ny * width + nx] {
+                bounds = Some(match bounds {
+                    Some((min, max)) => (min.inf(&color), max.sup(&color)),
+                    None => (color, color),
+                });
+            }
+        }
+    }
+    bounds
+}
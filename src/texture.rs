@@ -4,6 +4,7 @@ use crate::color::Color;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Texture {
     pub data: Vec<Color>,
+    pub alpha: Vec<u8>,
     pub width: usize,
     pub height: usize,
 }
@@ -21,22 +22,26 @@ impl Color {
 }
 
 impl Texture {
-    pub fn load_from_file(path: &str) -> Self {
-        let img = image::open(path).expect("Failed to load texture");
+    // Falla con un mensaje si el archivo no existe o no es una imagen que `image` sepa leer
+    pub fn try_load(path: &str) -> Result<Self, String> {
+        let img = image::open(path).map_err(|e| format!("no se pudo cargar la textura `{}`: {}", path, e))?;
         let (width, height) = img.dimensions();
         let mut data = Vec::new();
+        let mut alpha = Vec::new();
 
         for (_, _, pixel) in img.pixels() {
             let rgba = pixel.0;
             let color = Color::new(rgba[0], rgba[1], rgba[2]);
             data.push(color);
+            alpha.push(rgba[3]);
         }
 
-        Texture {
+        Ok(Texture {
             width: width as usize,
             height: height as usize,
             data,
-        }
+            alpha,
+        })
     }
 
     pub fn width(&self) -> usize {
@@ -56,4 +61,10 @@ impl Texture {
         let y = (v * (self.height - 1) as f32) as usize;
         self.get_pixel(x, y)
     }
+
+    pub fn get_alpha(&self, u: f32, v: f32) -> u8 {
+        let x = (u * (self.width - 1) as f32) as usize;
+        let y = (v * (self.height - 1) as f32) as usize;
+        self.alpha[y * self.width + x]
+    }
 }
@@ -0,0 +1,145 @@
+use nalgebra::Vector3;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use crate::aov::AovPixel;
+use crate::camera::Camera;
+use crate::light::Light;
+use crate::ray_intersect::RayIntersect;
+use crate::raytracer::{render_tile, RenderSettings};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+// Orden en que se reparten los tiles
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TileOrder {
+    // Anillos desde el centro de la imagen hacia afuera: lo importante aparece primero
+    Spiral,
+    // Curva Z: tiles vecinos se renderizan juntos y aprovechan mejor la caché
+    Morton,
+}
+
+impl TileOrder {
+    pub fn from_name(name: &str) -> Option<TileOrder> {
+        match name {
+            "spiral" => Some(TileOrder::Spiral),
+            "morton" => Some(TileOrder::Morton),
+            _ => None,
+        }
+    }
+}
+
+// Un tile terminado, listo para copiarlo a la imagen
+pub struct RenderedTile {
+    pub tile: Tile,
+    // Color lineal sin recortar a 8 bits, así el promedio de muchos cuadros no hace escalones
+    pub pixels: Vec<Vector3<f32>>,
+    pub aovs: Option<Vec<AovPixel>>,
+}
+
+pub struct TileScheduler {
+    pub tile_size: usize,
+    pub order: TileOrder,
+}
+
+impl Default for TileScheduler {
+    fn default() -> Self {
+        TileScheduler {
+            tile_size: 16,
+            order: TileOrder::Spiral,
+        }
+    }
+}
+
+impl TileScheduler {
+    pub fn tiles(&self, width: usize, height: usize) -> Vec<Tile> {
+        let size = self.tile_size.max(1);
+        let (columns, rows) = (width.div_ceil(size), height.div_ceil(size));
+        let mut tiles: Vec<(usize, usize)> = (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row))).collect();
+
+        match self.order {
+            TileOrder::Spiral => {
+                let (center_x, center_y) = ((columns as f32 - 1.0) / 2.0, (rows as f32 - 1.0) / 2.0);
+                let key = |&(column, row): &(usize, usize)| {
+                    let (dx, dy) = (column as f32 - center_x, row as f32 - center_y);
+                    (dx.abs().max(dy.abs()).round() as usize, dy.atan2(dx))
+                };
+                tiles.sort_by(|a, b| {
+                    let (a, b) = (key(a), key(b));
+                    a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+                });
+            }
+            TileOrder::Morton => tiles.sort_by_key(|&(column, row)| morton(column as u32, row as u32)),
+        }
+
+        tiles
+            .into_iter()
+            .map(|(column, row)| Tile {
+                x: column * size,
+                y: row * size,
+                width: size.min(width - column * size),
+                height: size.min(height - row * size),
+            })
+            .collect()
+    }
+
+    // Renderiza los tiles en orden repartiéndolos entre los hilos de rayon y manda cada uno
+    // por `sender` apenas termina. Con `cancel` en true los hilos no toman más tiles.
+    // Devuelve false si el cuadro quedó a medias.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(
+        &self,
+        width: usize,
+        height: usize,
+        objects: &[Arc<dyn RayIntersect>],
+        camera: &Camera,
+        lights: &[Light],
+        settings: &RenderSettings,
+        with_aovs: bool,
+        cancel: &AtomicBool,
+        sender: &Sender<RenderedTile>,
+    ) -> bool {
+        let tiles = self.tiles(width, height);
+        let next = AtomicUsize::new(0);
+
+        // Cada hilo toma el siguiente tile de la lista; así se respeta el orden, cosa que
+        // `par_iter` no hace porque parte la lista por la mitad
+        rayon::scope(|scope| {
+            for _ in 0..rayon::current_num_threads() {
+                scope.spawn(|_| {
+                    while !cancel.load(Ordering::Relaxed) {
+                        let Some(tile) = tiles.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                            break;
+                        };
+                        let (pixels, aovs) = render_tile(tile, width, height, objects, camera, lights, settings, with_aovs);
+                        // Si ya nadie recibe los tiles no tiene sentido seguir
+                        if sender.send(RenderedTile { tile: *tile, pixels, aovs }).is_err() {
+                            cancel.store(true, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+
+        !cancel.load(Ordering::Relaxed)
+    }
+}
+
+// Intercala los bits de x e y
+fn morton(x: u32, y: u32) -> u64 {
+    let spread = |value: u32| {
+        let mut value = value as u64;
+        value = (value | (value << 16)) & 0x0000_FFFF_0000_FFFF;
+        value = (value | (value << 8)) & 0x00FF_00FF_00FF_00FF;
+        value = (value | (value << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+        value = (value | (value << 2)) & 0x3333_3333_3333_3333;
+        (value | (value << 1)) & 0x5555_5555_5555_5555
+    };
+    spread(x) | (spread(y) << 1)
+}
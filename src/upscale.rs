@@ -0,0 +1,316 @@
+use rayon::prelude::*;
+use crate::aov::{AovPixel, Aovs};
+use crate::framebuffer::Framebuffer;
+
+// Cómo se lleva un cuadro de resolución reducida al tamaño de la ventana
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Upscaler {
+    Nearest,
+    Bilinear,
+    // Catmull-Rom, 4×4 muestras
+    Bicubic,
+    // Lanczos con a = 3, 6×6 muestras
+    Lanczos,
+    // Bicúbico que no mezcla superficies distintas según la profundidad y las normales
+    DepthGuided,
+}
+
+impl Upscaler {
+    pub fn next(self) -> Self {
+        match self {
+            Upscaler::Nearest => Upscaler::Bilinear,
+            Upscaler::Bilinear => Upscaler::Bicubic,
+            Upscaler::Bicubic => Upscaler::Lanczos,
+            Upscaler::Lanczos => Upscaler::DepthGuided,
+            Upscaler::DepthGuided => Upscaler::Nearest,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Upscaler::Nearest => "nearest",
+            Upscaler::Bilinear => "bilinear",
+            Upscaler::Bicubic => "bicubic",
+            Upscaler::Lanczos => "lanczos",
+            Upscaler::DepthGuided => "depth",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Upscaler> {
+        match name {
+            "nearest" => Some(Upscaler::Nearest),
+            "bilinear" => Some(Upscaler::Bilinear),
+            "bicubic" => Some(Upscaler::Bicubic),
+            "lanczos" => Some(Upscaler::Lanczos),
+            "depth" => Some(Upscaler::DepthGuided),
+            _ => None,
+        }
+    }
+
+    // Si necesita las pasadas auxiliares del cuadro chico
+    pub fn needs_guide(self) -> bool {
+        self == Upscaler::DepthGuided
+    }
+
+    // `guide` son la profundidad y las normales de `source`; sin ellas el modo guiado es bicúbico
+    pub fn upscale(self, source: &Framebuffer, width: usize, height: usize, guide: Option<&Aovs>) -> Framebuffer {
+        if (source.width, source.height) == (width, height) {
+            return Framebuffer {
+                width,
+                height,
+                buffer: source.buffer.clone(),
+            };
+        }
+        match self {
+            Upscaler::Nearest => nearest(source, width, height),
+            Upscaler::Bilinear => source.upscale_bilineal(width, height),
+            Upscaler::Bicubic | Upscaler::Lanczos => self.separable(source, width, height),
+            Upscaler::DepthGuided => match guide {
+                Some(guide) if (guide.width, guide.height) == (source.width, source.height) => guided(source, guide, width, height),
+                _ => Upscaler::Bicubic.separable(source, width, height),
+            },
+        }
+    }
+
+    // Radio del núcleo en pixeles de la imagen chica
+    fn radius(self) -> isize {
+        match self {
+            Upscaler::Nearest | Upscaler::Bilinear => 1,
+            Upscaler::Bicubic | Upscaler::DepthGuided => 2,
+            Upscaler::Lanczos => 3,
+        }
+    }
+
+    fn weight(self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            Upscaler::Nearest | Upscaler::Bilinear => (1.0 - x).max(0.0),
+            Upscaler::Bicubic | Upscaler::DepthGuided => {
+                if x < 1.0 {
+                    1.5 * x * x * x - 2.5 * x * x + 1.0
+                } else if x < 2.0 {
+                    -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+                } else {
+                    0.0
+                }
+            }
+            Upscaler::Lanczos => {
+                let a = 3.0;
+                if x < 1e-6 {
+                    1.0
+                } else if x < a {
+                    let pi_x = std::f32::consts::PI * x;
+                    a * pi_x.sin() * (pi_x / a).sin() / (pi_x * pi_x)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    // Para cada pixel de destino, las muestras de origen y sus pesos normalizados.
+    // Los centros de los pixeles quedan alineados entre las dos resoluciones.
+    fn taps(self, source_size: usize, target_size: usize) -> Vec<Vec<(usize, f32)>> {
+        let scale = source_size as f32 / target_size as f32;
+        (0..target_size)
+            .map(|target| {
+                let center = (target as f32 + 0.5) * scale - 0.5;
+                let first = center.floor() as isize - self.radius() + 1;
+                let mut taps: Vec<(usize, f32)> = (first..first + 2 * self.radius())
+                    .map(|sample| (sample.clamp(0, source_size as isize - 1) as usize, self.weight(center - sample as f32)))
+                    .collect();
+                let total: f32 = taps.iter().map(|&(_, weight)| weight).sum();
+                if total.abs() > 1e-6 {
+                    taps.iter_mut().for_each(|(_, weight)| *weight /= total);
+                }
+                taps
+            })
+            .collect()
+    }
+
+    // Primero filtra las filas y después las columnas
+    fn separable(self, source: &Framebuffer, width: usize, height: usize) -> Framebuffer {
+        let colors = unpack(source);
+        let taps_x = self.taps(source.width, width);
+        let taps_y = self.taps(source.height, height);
+
+        let mut horizontal = vec![[0.0; 3]; width * source.height];
+        horizontal.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            for (pixel, taps) in row.iter_mut().zip(&taps_x) {
+                for &(sample_x, weight) in taps {
+                    let color = colors[y * source.width + sample_x];
+                    (0..3).for_each(|c| pixel[c] += color[c] * weight);
+                }
+            }
+        });
+
+        let mut output = Framebuffer::new(width, height);
+        output.buffer.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let mut color = [0.0; 3];
+                for &(sample_y, weight) in &taps_y[y] {
+                    let sample = horizontal[sample_y * width + x];
+                    (0..3).for_each(|c| color[c] += sample[c] * weight);
+                }
+                *pixel = pack(color);
+            }
+        });
+        output
+    }
+}
+
+fn nearest(source: &Framebuffer, width: usize, height: usize) -> Framebuffer {
+    let mut output = Framebuffer::new(width, height);
+    output.buffer.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+        let source_y = y * source.height / height;
+        for (x, pixel) in row.iter_mut().enumerate() {
+            *pixel = source.buffer[source_y * source.width + x * source.width / width];
+        }
+    });
+    output
+}
+
+// Posiciones de las cuatro muestras centrales dentro de la ventana 4×4
+const CORNERS: [usize; 4] = [5, 6, 9, 10];
+// Parecido a partir del cual toda la ventana se considera la misma superficie
+const UNIFORM_SIMILARITY: f32 = 0.9;
+
+// Ventana 4×4 de la imagen chica alrededor de un pixel de destino
+struct Window {
+    colors: [[f32; 3]; 16],
+    // Parecido de cada muestra central con las 16 de la ventana
+    similarities: [[f32; 16]; 4],
+}
+
+// Bicúbico guiado (joint bilateral upsampling). Donde la ventana 4×4 cruza un borde, cada
+// superficie se interpola solo con sus propias muestras, así los colores de un bloque no
+// se corren al vecino ni al cielo. Dentro de una misma superficie queda el bicúbico normal.
+fn guided(source: &Framebuffer, guide: &Aovs, width: usize, height: usize) -> Framebuffer {
+    let mut output = Upscaler::Bicubic.separable(source, width, height);
+    let colors = unpack(source);
+    let (source_width, source_height) = (source.width, source.height);
+    let window_index = |x0: usize, y0: usize, tap: usize| {
+        let x = (x0 as isize - 1 + (tap % 4) as isize).clamp(0, source_width as isize - 1) as usize;
+        let y = (y0 as isize - 1 + (tap / 4) as isize).clamp(0, source_height as isize - 1) as usize;
+        y * source_width + x
+    };
+
+    // Los pixeles de destino comparten ventanas, así que los colores de cada ventana y el
+    // parecido de cada muestra central con las 16 se calculan una sola vez
+    let windows: Vec<Option<Window>> = (0..source_width * source_height)
+        .into_par_iter()
+        .map(|index| {
+            let (x0, y0) = (index % source_width, index / source_width);
+            let similarities: [[f32; 16]; 4] = CORNERS.map(|corner| {
+                let reference = &guide.pixels[window_index(x0, y0, corner)];
+                std::array::from_fn(|tap| similarity(reference, &guide.pixels[window_index(x0, y0, tap)]))
+            });
+            // Ventana de una sola superficie: el bicúbico ya sirve
+            if similarities.iter().flatten().all(|&similarity| similarity > UNIFORM_SIMILARITY) {
+                return None;
+            }
+            Some(Window {
+                colors: std::array::from_fn(|tap| colors[window_index(x0, y0, tap)]),
+                similarities,
+            })
+        })
+        .collect();
+
+    // Posición en la imagen chica y pesos bicúbicos de cada columna y cada fila de destino
+    let positions = |size: usize, source_size: usize| -> Vec<(usize, f32, [f32; 4])> {
+        let scale = source_size as f32 / size as f32;
+        (0..size)
+            .map(|target| {
+                let center = ((target as f32 + 0.5) * scale - 0.5).clamp(0.0, (source_size - 1) as f32);
+                let origin = center.floor() as usize;
+                let fraction = center - origin as f32;
+                (origin, fraction, std::array::from_fn(|tap| Upscaler::DepthGuided.weight(fraction + 1.0 - tap as f32)))
+            })
+            .collect()
+    };
+    let columns = positions(width, source_width);
+    let rows = positions(height, source_height);
+
+    output.buffer.par_chunks_mut(width).zip(rows.par_iter()).for_each(|(row, &(y0, fy, weights_y))| {
+        for (pixel, &(x0, fx, weights_x)) in row.iter_mut().zip(&columns) {
+            let Some(Window { colors, similarities }) = &windows[y0 * source_width + x0] else {
+                continue;
+            };
+
+            // Cada muestra central representa una superficie y cubre el peso bilineal de las
+            // muestras centrales parecidas a ella. El color final mezcla las superficies
+            // según esa cobertura (afilada), lo que suaviza la escalera de la rejilla chica.
+            let bilinear = [(1.0 - fx) * (1.0 - fy), fx * (1.0 - fy), (1.0 - fx) * fy, fx * fy];
+            let mut surfaces: Vec<usize> = Vec::with_capacity(4);
+            for corner in 0..4 {
+                if surfaces.iter().all(|&surface| similarities[surface][CORNERS[corner]] <= UNIFORM_SIMILARITY) {
+                    surfaces.push(corner);
+                }
+            }
+
+            let mut blended = [0.0; 3];
+            let mut coverage_total = 0.0;
+            for &surface in &surfaces {
+                let coverage: f32 = (0..4).map(|k| bilinear[k] * similarities[surface][CORNERS[k]]).sum::<f32>().powi(4);
+                if coverage < 1e-6 {
+                    continue;
+                }
+                let color = surface_color(colors, &similarities[surface], &weights_x, &weights_y).unwrap_or(colors[CORNERS[surface]]);
+                (0..3).for_each(|c| blended[c] += color[c] * coverage);
+                coverage_total += coverage;
+            }
+            if coverage_total > 0.0 {
+                *pixel = pack(blended.map(|channel| channel / coverage_total));
+            }
+        }
+    });
+    output
+}
+
+// Bicúbico de las muestras de la ventana parecidas a una superficie, recortado al rango
+// de esas muestras para no dejar halos
+fn surface_color(colors: &[[f32; 3]; 16], similarities: &[f32; 16], weights_x: &[f32; 4], weights_y: &[f32; 4]) -> Option<[f32; 3]> {
+    let mut sum = [0.0; 3];
+    let mut total = 0.0;
+    let (mut min, mut max) = ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]);
+    for (tap, (&similarity, color)) in similarities.iter().zip(colors).enumerate() {
+        if similarity < 1e-3 {
+            continue;
+        }
+        let weight = weights_x[tap % 4] * weights_y[tap / 4] * similarity;
+        for c in 0..3 {
+            sum[c] += color[c] * weight;
+            min[c] = min[c].min(color[c]);
+            max[c] = max[c].max(color[c]);
+        }
+        total += weight;
+    }
+    (total > 1e-4).then(|| [0, 1, 2].map(|c| (sum[c] / total).clamp(min[c], max[c])))
+}
+
+// Parecido entre dos muestras en [0, 1]: 1 si son la misma superficie
+fn similarity(a: &AovPixel, b: &AovPixel) -> f32 {
+    match (a.object_id == 0, b.object_id == 0) {
+        (true, true) => 1.0,
+        (true, false) | (false, true) => 0.0,
+        (false, false) => {
+            let cosine: f32 = (0..3).map(|c| a.normal[c] * b.normal[c]).sum();
+            let normal_weight = cosine.clamp(0.0, 1.0).powi(32);
+            let depth_difference = (a.depth - b.depth).abs() / a.depth.max(1e-3);
+            normal_weight * (-depth_difference / 0.1).exp()
+        }
+    }
+}
+
+fn unpack(framebuffer: &Framebuffer) -> Vec<[f32; 3]> {
+    framebuffer
+        .buffer
+        .iter()
+        .map(|&pixel| [(pixel >> 16) & 0xFF, (pixel >> 8) & 0xFF, pixel & 0xFF].map(|channel| channel as f32))
+        .collect()
+}
+
+fn pack(color: [f32; 3]) -> u32 {
+    let [r, g, b] = color.map(|channel| channel.round().clamp(0.0, 255.0) as u32);
+    (r << 16) | (g << 8) | b
+}